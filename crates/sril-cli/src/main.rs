@@ -1,38 +1,28 @@
-use std::io::{self, Write};
+use std::env;
+use std::process::ExitCode;
 
-fn main() -> io::Result<()> {
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let mut stderr = io::stderr();
-    let mut input = String::new();
-    let mut env = sril::env::Env::default();
-    write!(stdout, "Welcome to sril version 0.0.1 !\n\n")?;
+mod repl;
+mod run;
 
-    loop {
-        write!(stdout, "→  ")?;
-        stdout.flush()?;
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-        stdin.read_line(&mut input)?;
-
-        match run(input.trim(), &mut env) {
-            Ok(Some(val)) => writeln!(stdout, "{}", val)?,
-            Ok(None) => {}
-            Err(msg) => writeln!(stderr, "{}", msg)?,
+    match args.first().map(String::as_str) {
+        None => match repl::main() {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("error: {}", err);
+                ExitCode::FAILURE
+            }
+        },
+        Some("run") => run::main(&args[1..]),
+        Some("-h" | "--help" | "help") => {
+            println!("{}", run::USAGE);
+            ExitCode::SUCCESS
+        }
+        Some(command) => {
+            eprintln!("error: unknown command `{}`\n\n{}", command, run::USAGE);
+            ExitCode::from(2)
         }
-        input.clear();
-    }
-}
-
-fn run(input: &str, env: &mut sril::env::Env) -> Result<Option<sril::value::Value>, String> {
-    let parse = sril::parse(input).map_err(|msg| format!("Parse error: {}", msg))?;
-
-    let evaluated = parse
-        .eval(env)
-        .map_err(|msg| format!("Evaluation error: {}", msg))?;
-
-    if evaluated == sril::value::Value::Unit {
-        Ok(None)
-    } else {
-        Ok(Some(evaluated))
     }
 }
@@ -0,0 +1,298 @@
+use std::env;
+use std::fs;
+use std::mem;
+use std::path::PathBuf;
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use crate::run;
+
+const PROMPT: &str = "→  ";
+const CONTINUATION_PROMPT: &str = "…  ";
+const HISTORY_FILE: &str = ".sril_history";
+
+pub(crate) fn main() -> rustyline::Result<()> {
+    let mut editor = DefaultEditor::new()?;
+    let mut history = history_path();
+    if let Some(history) = &history {
+        // There is no history yet the first time the REPL is started.
+        let _ = editor.load_history(history);
+    }
+
+    let mut env = sril::env::Env::default();
+    let mut input = String::new();
+    println!("Welcome to sril version 0.0.1 !");
+    println!("Type :help for a list of commands.\n");
+
+    loop {
+        let prompt = if input.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                input.push_str(&line);
+                input.push('\n');
+                if !input.trim_start().starts_with(':') && is_incomplete(&input) {
+                    continue;
+                }
+
+                let entry = mem::take(&mut input);
+                let entry = entry.trim();
+                if entry.is_empty() {
+                    continue;
+                }
+
+                add_history_entry(&mut editor, &mut history, entry);
+
+                if !entry.starts_with(':') {
+                    eval("<repl>", entry, &env);
+                    continue;
+                }
+                match MetaCommand::parse(entry) {
+                    Some(MetaCommand::Quit) => return Ok(()),
+                    Some(command) => command.run(&mut env),
+                    None => eprintln!("error: unknown command `{}`, see :help", entry),
+                }
+            }
+            // Ctrl-C abandons the current entry, however many lines it has.
+            Err(ReadlineError::Interrupted) => input.clear(),
+            Err(ReadlineError::Eof) => return Ok(()),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+fn eval(name: &str, source: &str, env: &sril::env::Env) {
+    match sril::parse_named_program(name, source).and_then(|program| program.eval(env)) {
+        Ok(sril::value::Value::Unit) => {}
+        Ok(value) => println!("{}", value),
+        Err(err) => eprint!("{}", run::diagnostic(name, source, &err)),
+    }
+}
+
+const HELP: &str = "\
+Statements are evaluated as soon as they are complete; an entry with an open
+bracket, string or block comment continues on the next line.
+
+:help          show this message
+:quit          leave the REPL, as does Ctrl-D
+:env           list the current bindings
+:reset         forget all bindings
+:load <path>   run a script file in the current environment
+:ast <code>    show the syntax tree of a statement";
+
+#[derive(Debug, PartialEq)]
+enum MetaCommand<'a> {
+    Help,
+    Quit,
+    Env,
+    Reset,
+    Load(&'a str),
+    Ast(&'a str),
+}
+
+impl<'a> MetaCommand<'a> {
+    /// Parses an entry starting with `:`, returning `None` if it names no
+    /// command or has the wrong arguments for it.
+    fn parse(entry: &'a str) -> Option<Self> {
+        let (name, arg) = match entry.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (entry, ""),
+        };
+
+        match (name, arg) {
+            (":help", "") => Some(Self::Help),
+            (":quit", "") => Some(Self::Quit),
+            (":env", "") => Some(Self::Env),
+            (":reset", "") => Some(Self::Reset),
+            (":load", "") | (":ast", "") => None,
+            (":load", path) => Some(Self::Load(path)),
+            (":ast", code) => Some(Self::Ast(code)),
+            _ => None,
+        }
+    }
+
+    fn run(self, env: &mut sril::env::Env) {
+        match self {
+            Self::Help => println!("{}", HELP),
+            Self::Quit => {}
+            Self::Env => {
+                for (name, value) in env.bindings() {
+                    println!("{} = {}", name, value);
+                }
+            }
+            Self::Reset => *env = sril::env::Env::default(),
+            Self::Load(path) => match fs::read_to_string(path) {
+                Ok(source) => eval(path, &source, env),
+                Err(err) => eprintln!("error: cannot read {}: {}", path, err),
+            },
+            Self::Ast(code) => match sril::parse(code) {
+                Ok(parse) => println!("{:#?}", parse.statement()),
+                Err(err) => eprint!("{}", run::diagnostic("<ast>", code, &err)),
+            },
+        }
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    env::home_dir().map(|home| home.join(HISTORY_FILE))
+}
+
+/// Adds `entry` to the history and saves it. History is only a convenience,
+/// so a file that cannot be written is reported once and then no longer saved
+/// to, rather than ending the session.
+fn add_history_entry(editor: &mut DefaultEditor, history: &mut Option<PathBuf>, entry: &str) {
+    let saved = editor.add_history_entry(entry).and_then(|_| match history {
+        Some(path) => editor.save_history(path),
+        None => Ok(()),
+    });
+
+    if let Err(err) = saved {
+        if let Some(path) = history.take() {
+            eprintln!(
+                "warning: cannot save history to {}, it will not be kept: {}",
+                path.display(),
+                err
+            );
+        }
+    }
+}
+
+enum Open {
+    Delimiter(char),
+    String,
+    BlockComment,
+}
+
+/// Whether `input` stops inside a bracket, string or block comment, in which
+/// case the REPL asks for another line instead of evaluating it. Closing
+/// brackets that match nothing are left for the parser to report.
+fn is_incomplete(input: &str) -> bool {
+    let mut open = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match open.last() {
+            Some(Open::String) => match c {
+                '\\' => {
+                    chars.next();
+                }
+                '"' => {
+                    open.pop();
+                }
+                '{' => open.push(Open::Delimiter('}')),
+                _ => {}
+            },
+            Some(Open::BlockComment) => match (c, chars.peek()) {
+                ('*', Some('/')) => {
+                    chars.next();
+                    open.pop();
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    open.push(Open::BlockComment);
+                }
+                _ => {}
+            },
+            Some(Open::Delimiter(_)) | None => match (c, chars.peek()) {
+                ('"', _) => open.push(Open::String),
+                ('(', _) => open.push(Open::Delimiter(')')),
+                ('[', _) => open.push(Open::Delimiter(']')),
+                ('{', _) => open.push(Open::Delimiter('}')),
+                (')' | ']' | '}', _) => match open.last() {
+                    Some(Open::Delimiter(closer)) if *closer == c => {
+                        open.pop();
+                    }
+                    _ => return false,
+                },
+                ('/', Some('/')) => {
+                    chars.by_ref().find(|&c| c == '\n');
+                }
+                ('/', Some('*')) => {
+                    chars.next();
+                    open.push(Open::BlockComment);
+                }
+                _ => {}
+            },
+        }
+    }
+
+    !open.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustyline::history::History;
+
+    #[test]
+    fn parse_meta_commands() {
+        assert_eq!(MetaCommand::parse(":quit"), Some(MetaCommand::Quit));
+        assert_eq!(MetaCommand::parse(":env"), Some(MetaCommand::Env));
+        assert_eq!(
+            MetaCommand::parse(":load  scripts/build.sril "),
+            Some(MetaCommand::Load("scripts/build.sril")),
+        );
+        assert_eq!(
+            MetaCommand::parse(":ast let x = [1, 2]"),
+            Some(MetaCommand::Ast("let x = [1, 2]")),
+        );
+    }
+
+    #[test]
+    fn reject_unknown_or_malformed_meta_commands() {
+        assert_eq!(MetaCommand::parse(":exit"), None);
+        assert_eq!(MetaCommand::parse(":quit now"), None);
+        assert_eq!(MetaCommand::parse(":load"), None);
+        assert_eq!(MetaCommand::parse(":ast "), None);
+    }
+
+    #[test]
+    fn stop_saving_history_that_cannot_be_written() {
+        let mut editor = DefaultEditor::new().unwrap();
+        let mut history = Some(PathBuf::from("/nonexistent/dir").join(HISTORY_FILE));
+
+        add_history_entry(&mut editor, &mut history, "1 + 1");
+        assert_eq!(history, None);
+
+        add_history_entry(&mut editor, &mut history, "2 + 2");
+        assert_eq!(editor.history().len(), 2);
+    }
+
+    #[test]
+    fn complete_input() {
+        assert!(!is_incomplete("1 + 2\n"));
+        assert!(!is_incomplete("fn f(x) { [x, \"}\"] } // {\n"));
+        assert!(!is_incomplete("\"a {1 + {2}} b\"\n"));
+    }
+
+    #[test]
+    fn unclosed_brackets() {
+        assert!(is_incomplete("fn f(x) {\n"));
+        assert!(is_incomplete("[1,\n2\n"));
+        assert!(is_incomplete("f(\n"));
+    }
+
+    #[test]
+    fn unclosed_string() {
+        assert!(is_incomplete("\"line\n"));
+        assert!(is_incomplete("\"escaped quote \\\"\n"));
+        assert!(is_incomplete("\"{ {1}\n"));
+    }
+
+    #[test]
+    fn unclosed_block_comment() {
+        assert!(is_incomplete("1 /* a /* nested */ comment\n"));
+        assert!(!is_incomplete("1 /* a /* nested */ comment */\n"));
+    }
+
+    #[test]
+    fn unmatched_closer_is_left_to_parser() {
+        assert!(!is_incomplete("{ 1 ]\n"));
+        assert!(!is_incomplete(")\n"));
+    }
+}
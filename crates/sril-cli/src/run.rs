@@ -0,0 +1,236 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use sril::error::Span;
+
+pub(crate) const USAGE: &str = "\
+usage: sril-cli                           start the REPL
+       sril-cli run [--print] <path>      run a script file
+       sril-cli run [--print] -e <code>   run the given code
+
+options:
+  -p, --print   print the value of the last statement";
+
+#[derive(Debug, PartialEq)]
+enum Source {
+    File(PathBuf),
+    Code(String),
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    source: Source,
+    print: bool,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut source = None;
+        let mut print = false;
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let next_source = match arg.as_str() {
+                "-p" | "--print" => {
+                    print = true;
+                    continue;
+                }
+                "-e" => match args.next() {
+                    Some(code) => Source::Code(code.clone()),
+                    None => return Err("`-e` needs the code to run".to_string()),
+                },
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("unknown option `{}`", flag))
+                }
+                path => Source::File(PathBuf::from(path)),
+            };
+
+            if source.replace(next_source).is_some() {
+                return Err("only one script can be run at a time".to_string());
+            }
+        }
+
+        match source {
+            Some(source) => Ok(Self { source, print }),
+            None => Err("nothing to run, pass a path or `-e <code>`".to_string()),
+        }
+    }
+}
+
+/// Runs `sril-cli run` with the arguments that follow `run`.
+pub(crate) fn main(args: &[String]) -> ExitCode {
+    let options = match Options::parse(args) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("error: {}\n\n{}", msg, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let (name, source) = match options.source {
+        Source::File(path) => match fs::read_to_string(&path) {
+            Ok(source) => (path.display().to_string(), source),
+            Err(err) => {
+                eprintln!("error: cannot read {}: {}", path.display(), err);
+                return ExitCode::FAILURE;
+            }
+        },
+        Source::Code(code) => ("<code>".to_string(), code),
+    };
+
+    let result = sril::parse_named_program(&name, &source)
+        .and_then(|program| program.eval(&sril::env::Env::default()));
+
+    match result {
+        Ok(value) => {
+            if options.print && value != sril::value::Value::Unit {
+                println!("{}", value);
+            }
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprint!("{}", diagnostic(&name, &source, &err));
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Formats `err` together with the line of `source`, the code called `name`,
+/// it points at, marking the offending part with carets. A runtime error is
+/// shown against the code it happened in instead, which for one in a function
+/// is where that function was defined.
+pub(crate) fn diagnostic(name: &str, source: &str, err: &sril::Error) -> String {
+    let mut out = format!("error: {}\n", err);
+
+    let (name, source) = match err {
+        sril::Error::Runtime(sril::error::RuntimeError {
+            source: Some(code), ..
+        }) => (code.name.as_deref().unwrap_or(name), code.text.as_str()),
+        _ => (name, source),
+    };
+
+    let Some(span) = err.span() else {
+        return out;
+    };
+    let line = source.lines().nth(span.line - 1).unwrap_or("");
+    let gutter = " ".repeat(span.line.to_string().len());
+
+    let _ = writeln!(out, "{}--> {}:{}", gutter, name, span);
+    let _ = writeln!(out, "{} |", gutter);
+    let _ = writeln!(out, "{} | {}", span.line, line);
+    let _ = writeln!(
+        out,
+        "{} | {}{}",
+        gutter,
+        " ".repeat(span.column - 1),
+        "^".repeat(marked_width(source, span, line)),
+    );
+
+    out
+}
+
+// The span is only marked up to the end of its first line, and always with at
+// least one caret so that errors at the end of input are visible too.
+fn marked_width(source: &str, span: Span, line: &str) -> usize {
+    let remaining_on_line = line.chars().count().saturating_sub(span.column - 1);
+    let spanned = source
+        .get(span.start..span.end)
+        .map_or(0, |spanned| spanned.chars().count());
+
+    spanned.min(remaining_on_line).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_path_with_print_flag() {
+        assert_eq!(
+            Options::parse(&args(&["--print", "build.sril"])),
+            Ok(Options {
+                source: Source::File(PathBuf::from("build.sril")),
+                print: true,
+            }),
+        );
+    }
+
+    #[test]
+    fn parse_inline_code() {
+        assert_eq!(
+            Options::parse(&args(&["-e", "1 + 2", "-p"])),
+            Ok(Options {
+                source: Source::Code("1 + 2".to_string()),
+                print: true,
+            }),
+        );
+    }
+
+    #[test]
+    fn reject_missing_or_extra_sources() {
+        assert!(Options::parse(&args(&[])).is_err());
+        assert!(Options::parse(&args(&["-e"])).is_err());
+        assert!(Options::parse(&args(&["a.sril", "-e", "1"])).is_err());
+        assert!(Options::parse(&args(&["--verbose", "a.sril"])).is_err());
+    }
+
+    #[test]
+    fn format_runtime_error() {
+        let source = "let a = 1\nlet b = a / 0\n";
+        let err = sril::parse_program(source)
+            .unwrap()
+            .eval(&sril::env::Env::default())
+            .unwrap_err();
+
+        assert_eq!(
+            diagnostic("div.sril", source, &err),
+            "\
+error: runtime error: division by zero at 2:1
+ --> div.sril:2:1
+  |
+2 | let b = a / 0
+  | ^^^^^^^^^^^^^
+",
+        );
+    }
+
+    #[test]
+    fn format_runtime_error_in_function_defined_earlier() {
+        let env = sril::env::Env::default();
+        sril::parse_named_program("lib.sril", "fn f(x) {\n  let y = 2\n  y / x\n}")
+            .unwrap()
+            .eval(&env)
+            .unwrap();
+
+        let source = "f(0)";
+        let err = sril::parse_named_program("<repl>", source)
+            .unwrap()
+            .eval(&env)
+            .unwrap_err();
+
+        assert_eq!(
+            diagnostic("<repl>", source, &err),
+            "\
+error: runtime error: division by zero at 3:3
+ --> lib.sril:3:3
+  |
+3 |   y / x
+  |   ^^^^^
+",
+        );
+    }
+
+    #[test]
+    fn format_parse_error_at_end_of_input() {
+        let source = "(1 + 2";
+        let err = sril::parse_program(source).unwrap_err();
+
+        assert!(diagnostic("<code>", source, &err).ends_with("1 | (1 + 2\n  |       ^\n"));
+    }
+}
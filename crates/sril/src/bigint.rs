@@ -0,0 +1,131 @@
+//! Support for the `bigint` feature: integer results that no longer fit in an
+//! `i64` are promoted to arbitrary-precision integers instead of overflowing.
+
+pub use num_bigint::BigInt;
+use num_bigint::Sign;
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::value::Value;
+
+/// Turns a big integer back into a plain number whenever it fits, so that
+/// every integer has exactly one representation and equality keeps working.
+pub(crate) fn normalize(n: BigInt) -> Value {
+    match n.to_i64() {
+        Some(n) => Value::Number(n),
+        None => Value::BigInt(n),
+    }
+}
+
+/// Converts to the nearest float, which is infinite for huge integers.
+pub(crate) fn to_f64(n: &BigInt) -> f64 {
+    // `num-bigint` saturates already, so this fallback saturates the same way.
+    n.to_f64().unwrap_or(match n.sign() {
+        Sign::Minus => f64::NEG_INFINITY,
+        _ => f64::INFINITY,
+    })
+}
+
+/// Truncates a finite float to a big integer.
+pub(crate) fn from_f64(x: f64) -> Option<BigInt> {
+    BigInt::from_f64(x.trunc())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::Env;
+    use crate::expression::Expression;
+
+    fn eval_str(s: &str) -> Value {
+        let (_, expression) = Expression::new(s).unwrap();
+        expression.eval(&Env::default()).unwrap()
+    }
+
+    fn big(s: &str) -> Value {
+        Value::BigInt(s.parse().unwrap())
+    }
+
+    #[test]
+    fn promote_on_overflow() {
+        assert_eq!(
+            eval_str("9223372036854775807 + 1"),
+            big("9223372036854775808")
+        );
+        assert_eq!(
+            eval_str("4294967296 * 4294967296"),
+            big("18446744073709551616")
+        );
+    }
+
+    #[test]
+    fn promote_on_negation_overflow() {
+        assert_eq!(
+            eval_str("-(-9223372036854775808)"),
+            big("9223372036854775808")
+        );
+    }
+
+    #[test]
+    fn demote_when_result_fits_again() {
+        assert_eq!(
+            eval_str("(9223372036854775807 + 1) - 1"),
+            Value::Number(i64::MAX)
+        );
+        assert_eq!(
+            eval_str("4294967296 * 4294967296 / 4294967296"),
+            Value::Number(4294967296)
+        );
+    }
+
+    #[test]
+    fn compare_big_ints_with_numbers() {
+        assert_eq!(
+            eval_str("9223372036854775807 * 2 > 9223372036854775807"),
+            Value::Bool(true),
+        );
+    }
+
+    #[test]
+    fn mix_big_ints_with_floats() {
+        assert_eq!(
+            eval_str("9223372036854775807 * 2 / 2.0"),
+            Value::Float(9223372036854775807.0),
+        );
+    }
+
+    #[test]
+    fn parse_literals_outside_i64() {
+        assert_eq!(
+            Expression::new("18446744073709551616"),
+            Ok(("", Expression::BigInt(BigInt::from(1u128 << 64)))),
+        );
+        assert_eq!(
+            eval_str("-9223372036854775809 + 1"),
+            Value::Number(i64::MIN)
+        );
+    }
+
+    #[test]
+    fn parse_printed_big_int_back() {
+        let printed = eval_str("4294967296 * 4294967296 * -3").to_string();
+
+        assert_eq!(printed, "-55340232221128654848");
+        assert_eq!(
+            eval_str(&format!("{} / -3", printed)),
+            big("18446744073709551616")
+        );
+    }
+
+    #[test]
+    fn convert_huge_int_to_infinite_float() {
+        let huge = BigInt::from(1) << 1100;
+
+        assert_eq!(to_f64(&huge), f64::INFINITY);
+        assert_eq!(to_f64(&-huge), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn convert_large_float_to_int() {
+        assert_eq!(eval_str("int(1e19)"), big("10000000000000000000"));
+    }
+}
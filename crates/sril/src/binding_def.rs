@@ -1,23 +1,51 @@
+use crate::control_flow::ControlFlow;
 use crate::env::Env;
+use crate::error::ParseFailure;
 use crate::expression::Expression;
 use crate::utils;
 use crate::value::Value;
 
-#[derive(Debug, PartialEq)]
-pub enum Identifier {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum BindingKind {
+    /// `let`: immutable, may be shadowed.
     Let,
+    /// `let mut`: may be reassigned.
+    LetMut,
+    /// `const`: immutable and may not be redefined in the same scope.
     Const,
 }
 
+impl BindingKind {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        if let Ok(s) = utils::extract_keyword("const", s) {
+            return Ok((s, Self::Const));
+        }
+
+        let s = utils::extract_keyword("let", s)
+            .map_err(|err| err.or(ParseFailure::new(s, "`const`")))?;
+        let (after_whitespace, _) = utils::extract_required_whitespaces(s)?;
+
+        match utils::extract_keyword("mut", after_whitespace) {
+            Ok(s) => Ok((s, Self::LetMut)),
+            Err(_) => Ok((s, Self::Let)),
+        }
+    }
+
+    pub(crate) fn is_mutable(self) -> bool {
+        self == Self::LetMut
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct BindingDef {
+    pub kind: BindingKind,
     pub name: String,
     pub val: Expression,
 }
 
 impl BindingDef {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
-        let s = utils::extract_tag("let", s)?;
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let (s, kind) = BindingKind::new(s)?;
         let (s, _) = utils::extract_required_whitespaces(s)?;
 
         let (s, name) = utils::extract_identifier(s)?;
@@ -30,14 +58,16 @@ impl BindingDef {
         Ok((
             s,
             Self {
+                kind,
                 name: name.to_string(),
                 val,
             },
         ))
     }
 
-    pub(crate) fn eval(&self, env: &mut Env) -> Result<Value, String> {
-        env.store_binding(self.name.clone(), self.val.eval(env)?);
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let value = self.val.eval(env)?;
+        env.store_binding(self.name.clone(), value, self.kind)?;
         Ok(Value::Unit)
     }
 }
@@ -45,6 +75,7 @@ impl BindingDef {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::RuntimeErrorKind;
     use crate::expression::{Number, Operator};
 
     #[test]
@@ -54,10 +85,11 @@ mod tests {
             Ok((
                 "",
                 BindingDef {
+                    kind: BindingKind::Let,
                     name: "a".to_string(),
                     val: Expression::Operation {
-                        lhs: Number(10),
-                        rhs: Number(2),
+                        lhs: Box::new(Expression::Number(Number(10))),
+                        rhs: Box::new(Expression::Number(Number(2))),
                         op: Operator::Div,
                     },
                 },
@@ -69,15 +101,68 @@ mod tests {
     fn cannot_parse_binding_def_without_space_after_let() {
         assert_eq!(
             BindingDef::new("letaaa=1+2"),
-            Err("Expected space".to_string()),
+            Err(ParseFailure::new("letaaa=1+2", "`let`")
+                .or(ParseFailure::new("letaaa=1+2", "`const`"))),
+        );
+    }
+
+    #[test]
+    fn parse_mutable_binding_def() {
+        assert_eq!(
+            BindingDef::new("let mut count = 0"),
+            Ok((
+                "",
+                BindingDef {
+                    kind: BindingKind::LetMut,
+                    name: "count".to_string(),
+                    val: Expression::Number(Number(0)),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_const_binding_def() {
+        assert_eq!(
+            BindingDef::new("const max = 10"),
+            Ok((
+                "",
+                BindingDef {
+                    kind: BindingKind::Const,
+                    name: "max".to_string(),
+                    val: Expression::Number(Number(10)),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_binding_named_like_mut() {
+        assert_eq!(
+            BindingDef::new("let mutable = 1").map(|(_, bd)| (bd.kind, bd.name)),
+            Ok((BindingKind::Let, "mutable".to_string())),
         );
     }
 
-    // #[test]
-    // fn eval_number_expression() {
-    //     let mut env = Env::default();
-    //     let (_, bd) = BindingDef::new("let a = 10 / 2");
-    //     bd.eval(&mut env);
-    //     assert_eq!(env.retrieve_bindings(bd.name), &Value::Number(5))
-    // }
+    #[test]
+    fn eval_binding_def() {
+        let env = Env::default();
+        let (_, bd) = BindingDef::new("let a = 10 / 2").unwrap();
+
+        assert_eq!(bd.eval(&env), Ok(Value::Unit));
+        assert_eq!(env.get_binding_value("a"), Ok(Value::Number(5)));
+    }
+
+    #[test]
+    fn eval_const_redefinition() {
+        let env = Env::default();
+        let (_, bd) = BindingDef::new("const a = 1").unwrap();
+        bd.eval(&env).unwrap();
+
+        let (_, bd) = BindingDef::new("let a = 2").unwrap();
+        assert_eq!(
+            bd.eval(&env),
+            Err(RuntimeErrorKind::ConstRedefinition("a".to_string()).into()),
+        );
+    }
 }
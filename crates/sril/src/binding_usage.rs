@@ -1,4 +1,6 @@
+use crate::builtin::Builtin;
 use crate::env::Env;
+use crate::error::{ParseFailure, RuntimeError};
 use crate::utils;
 use crate::value::Value;
 
@@ -8,7 +10,7 @@ pub struct BindingUsage {
 }
 
 impl BindingUsage {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
         let (s, name) = utils::extract_identifier(s)?;
         Ok((
             s,
@@ -18,14 +20,20 @@ impl BindingUsage {
         ))
     }
 
-    pub(crate) fn eval(&self, env: &Env) -> Result<Value, String> {
-        env.get_binding_value(&self.name)
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, RuntimeError> {
+        env.get_binding_value(&self.name).or_else(|err| {
+            Builtin::from_name(&self.name)
+                .map(Value::Builtin)
+                .ok_or(err)
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::binding_def::BindingKind;
+    use crate::error::RuntimeErrorKind;
 
     #[test]
     fn binding_usage() {
@@ -42,28 +50,55 @@ mod tests {
 
     #[test]
     fn eval_existing_binding_usage() {
-        let mut env = Env::default();
-        env.store_binding("foo".to_string(), Value::Number(10));
+        let env = Env::default();
+        env.store_binding("foo".to_string(), Value::Number(10), BindingKind::Let)
+            .unwrap();
 
         assert_eq!(
             BindingUsage {
                 name: "foo".to_string(),
             }
-            .eval(&mut env),
+            .eval(&env),
             Ok(Value::Number(10)),
         );
     }
 
     #[test]
     fn eval_non_existent_binding_usage() {
-        let mut empty_env = Env::default();
+        let empty_env = Env::default();
 
         assert_eq!(
             BindingUsage {
                 name: "i_dont_exist".to_string(),
             }
-            .eval(&mut empty_env),
-            Err("binding with name ‘i_dont_exist’ does not exist".to_string()),
+            .eval(&empty_env),
+            Err(RuntimeErrorKind::UndefinedBinding("i_dont_exist".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn eval_builtin_binding_usage() {
+        assert_eq!(
+            BindingUsage {
+                name: "float".to_string(),
+            }
+            .eval(&Env::default()),
+            Ok(Value::Builtin(Builtin::Float)),
+        );
+    }
+
+    #[test]
+    fn eval_binding_shadowing_builtin() {
+        let env = Env::default();
+        env.store_binding("int".to_string(), Value::Number(1), BindingKind::Let)
+            .unwrap();
+
+        assert_eq!(
+            BindingUsage {
+                name: "int".to_string(),
+            }
+            .eval(&env),
+            Ok(Value::Number(1)),
         );
     }
 }
@@ -1,53 +1,102 @@
-use crate::{env::Env, statement::Statement, utils, value::Value};
+use std::fmt;
+
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::{Location, ParseFailure};
+use crate::statement::Statement;
+use crate::utils;
+use crate::value::Value;
 
-#[derive(Debug, PartialEq)]
 pub struct Block {
-    statements: Vec<Statement>,
+    pub(crate) statements: Vec<Statement>,
+    /// Where each statement is, so a runtime error can point at the one that
+    /// failed.
+    pub(crate) locations: Vec<Location>,
 }
 
 impl Block {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
         let s = utils::extract_tag("{", s)?;
-        let (s, _) = utils::extract_whitespaces(s);
+        let (mut s, _) =
+            utils::extract_statement_separator(s).unwrap_or_else(|_| utils::extract_whitespaces(s));
 
-        let mut s = s;
         let mut statements = Vec::new();
+        let mut locations = Vec::new();
 
-        while let Ok((new_s, stmt)) = Statement::new(s) {
-            s = new_s;
-            statements.push(stmt);
+        // Stops at the first thing that is not a statement, or at the first
+        // statement that is not followed by a separator; either way only the
+        // closing brace may come next.
+        let statement_err = loop {
+            let (after_statement, statement) = match Statement::new(s) {
+                Ok(parsed) => parsed,
+                Err(err) => break err,
+            };
+            statements.push(statement);
+            locations.push(Location::new(s, after_statement));
 
-            let (new_s, _) = utils::extract_whitespaces(s);
-            s = new_s;
-        }
+            match utils::extract_statement_separator(after_statement) {
+                Ok((after_separator, _)) => s = after_separator,
+                Err(err) => {
+                    s = after_statement;
+                    break err;
+                }
+            }
+        };
 
         let (s, _) = utils::extract_whitespaces(s);
-        let s = utils::extract_tag("}", s)?;
+        let s = utils::extract_tag("}", s).map_err(|err| statement_err.or(err))?;
 
-        Ok((s, Self { statements }))
+        Ok((
+            s,
+            Self {
+                statements,
+                locations,
+            },
+        ))
     }
 
-    pub(crate) fn eval(&self, parent_env: &Env) -> Result<Value, String> {
+    pub(crate) fn eval(&self, parent_env: &Env) -> Result<Value, ControlFlow> {
         if self.statements.is_empty() {
             return Ok(Value::Unit);
         }
 
-        let mut env = parent_env.create_child();
-
-        let statements_except_last = &self.statements[..self.statements.len() - 1];
+        let _eval = parent_env.enter_eval()?;
+        let env = parent_env.create_child();
+        let mut value = Value::Unit;
 
-        for statement in statements_except_last {
-            statement.eval(&mut env)?;
+        for (idx, statement) in self.statements.iter().enumerate() {
+            value = statement
+                .eval(&env)
+                .map_err(|flow| match self.locations.get(idx) {
+                    Some(location) => flow.with_location(*location),
+                    None => flow,
+                })?;
         }
 
-        self.statements.last().unwrap().eval(&mut env)
+        Ok(value)
+    }
+}
+
+// Locations are noise when looking at the syntax tree, so they are left out.
+impl fmt::Debug for Block {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Block")
+            .field("statements", &self.statements)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Blocks are compared by their statements, wherever they are in the source.
+impl PartialEq for Block {
+    fn eq(&self, other: &Self) -> bool {
+        self.statements == other.statements
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::binding_def::BindingDef;
+    use crate::binding_def::{BindingDef, BindingKind};
     use crate::binding_usage::BindingUsage;
     use crate::env::Env;
     use crate::expression::{Expression, Number, Operator};
@@ -59,7 +108,8 @@ mod tests {
             Ok((
                 "",
                 Block {
-                    statements: Vec::new()
+                    statements: Vec::new(),
+                    locations: Vec::new()
                 }
             ))
         );
@@ -72,7 +122,8 @@ mod tests {
             Ok((
                 "",
                 Block {
-                    statements: Vec::new()
+                    statements: Vec::new(),
+                    locations: Vec::new()
                 }
             ))
         );
@@ -86,6 +137,7 @@ mod tests {
                 "",
                 Block {
                     statements: vec![Statement::Expression(Expression::Number(Number(5)))],
+                    locations: Vec::new(),
                 },
             )),
         );
@@ -106,10 +158,12 @@ mod tests {
                 Block {
                     statements: vec![
                         Statement::BindingDef(BindingDef {
+                            kind: BindingKind::Let,
                             name: "a".to_string(),
                             val: Expression::Number(Number(10)),
                         }),
                         Statement::BindingDef(BindingDef {
+                            kind: BindingKind::Let,
                             name: "b".to_string(),
                             val: Expression::BindingUsage(BindingUsage {
                                 name: "a".to_string()
@@ -119,6 +173,7 @@ mod tests {
                             name: "b".to_string(),
                         })),
                     ],
+                    locations: Vec::new(),
                 },
             )),
         );
@@ -129,6 +184,7 @@ mod tests {
         assert_eq!(
             Expression::Block(Block {
                 statements: vec![Statement::Expression(Expression::Number(Number(10)))],
+                locations: Vec::new(),
             })
             .eval(&Env::default()),
             Ok(Value::Number(10)),
@@ -139,7 +195,8 @@ mod tests {
     fn eval_empty_block() {
         assert_eq!(
             Block {
-                statements: Vec::new()
+                statements: Vec::new(),
+                locations: Vec::new()
             }
             .eval(&Env::default()),
             Ok(Value::Unit),
@@ -151,6 +208,7 @@ mod tests {
         assert_eq!(
             Block {
                 statements: vec![Statement::Expression(Expression::Number(Number(25)))],
+                locations: Vec::new(),
             }
             .eval(&Env::default()),
             Ok(Value::Number(25)),
@@ -163,6 +221,7 @@ mod tests {
             Block {
                 statements: vec![
                     Statement::BindingDef(BindingDef {
+                        kind: BindingKind::Let,
                         name: "one".to_string(),
                         val: Expression::Number(Number(1)),
                     }),
@@ -170,6 +229,7 @@ mod tests {
                         name: "one".to_string(),
                     })),
                 ],
+                locations: Vec::new(),
             }
             .eval(&Env::default()),
             Ok(Value::Number(1)),
@@ -182,18 +242,22 @@ mod tests {
             Block {
                 statements: vec![
                     Statement::BindingDef(BindingDef {
+                        kind: BindingKind::Let,
                         name: "foo".to_string(),
                         val: Expression::Number(Number(5)),
                     }),
                     Statement::BindingDef(BindingDef {
+                        kind: BindingKind::Let,
                         name: "bar".to_string(),
                         val: Expression::Number(Number(4)),
                     }),
                     Statement::BindingDef(BindingDef {
+                        kind: BindingKind::Let,
                         name: "baz".to_string(),
                         val: Expression::Number(Number(3)),
                     }),
                 ],
+                locations: Vec::new(),
             }
             .eval(&Env::default()),
             Ok(Value::Unit),
@@ -208,11 +272,12 @@ mod tests {
                     Statement::Expression(Expression::Number(Number(100))),
                     Statement::Expression(Expression::Number(Number(30))),
                     Statement::Expression(Expression::Operation {
-                        lhs: Number(10),
-                        rhs: Number(7),
+                        lhs: Box::new(Expression::Number(Number(10))),
+                        rhs: Box::new(Expression::Number(Number(7))),
                         op: Operator::Sub,
                     }),
                 ],
+                locations: Vec::new(),
             }
             .eval(&Env::default()),
             Ok(Value::Number(3)),
@@ -221,8 +286,9 @@ mod tests {
 
     #[test]
     fn eval_block_with_env_already_created() {
-        let mut env = Env::default();
-        env.store_binding("x".to_string(), Value::Number(12));
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(12), BindingKind::Let)
+            .unwrap();
 
         assert_eq!(
             Block {
@@ -231,9 +297,34 @@ mod tests {
                         name: "x".to_string()
                     }
                 ))],
+                locations: Vec::new(),
             }
             .eval(&env),
             Ok(Value::Number(12)),
         );
     }
+
+    #[test]
+    fn eval_block_mutating_outer_binding() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::LetMut)
+            .unwrap();
+
+        let (_, block) = Block::new("{ x = x + 1; { x *= 10 } }").unwrap();
+        block.eval(&env).unwrap();
+
+        assert_eq!(env.get_binding_value("x"), Ok(Value::Number(20)));
+    }
+
+    #[test]
+    fn eval_block_shadowing_does_not_leak() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::LetMut)
+            .unwrap();
+
+        let (_, block) = Block::new("{ let mut x = 5; x = 6; x }").unwrap();
+
+        assert_eq!(block.eval(&env), Ok(Value::Number(6)));
+        assert_eq!(env.get_binding_value("x"), Ok(Value::Number(1)));
+    }
 }
@@ -0,0 +1,304 @@
+use std::rc::Rc;
+
+#[cfg(feature = "bigint")]
+use crate::bigint;
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::value::Value;
+
+/// A function provided by the interpreter itself. Builtins are looked up by
+/// name only after every scope has been searched, so a script can shadow
+/// them with its own bindings.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Builtin {
+    Int,
+    Float,
+    Len,
+    Push,
+    Insert,
+    Keys,
+    Values,
+}
+
+impl Builtin {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "int" => Some(Self::Int),
+            "float" => Some(Self::Float),
+            "len" => Some(Self::Len),
+            "push" => Some(Self::Push),
+            "insert" => Some(Self::Insert),
+            "keys" => Some(Self::Keys),
+            "values" => Some(Self::Values),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::Len => "len",
+            Self::Push => "push",
+            Self::Insert => "insert",
+            Self::Keys => "keys",
+            Self::Values => "values",
+        }
+    }
+
+    fn arity(self) -> usize {
+        match self {
+            Self::Int | Self::Float | Self::Len | Self::Keys | Self::Values => 1,
+            Self::Push => 2,
+            Self::Insert => 3,
+        }
+    }
+
+    fn check_arity(self, found: usize) -> Result<(), RuntimeError> {
+        if found == self.arity() {
+            return Ok(());
+        }
+
+        Err(RuntimeErrorKind::ArityMismatch {
+            name: self.name().to_string(),
+            expected: self.arity(),
+            found,
+        }
+        .into())
+    }
+
+    pub(crate) fn call(self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.check_arity(args.len())?;
+
+        let mut args = args.into_iter();
+        let mut arg = args.next().unwrap();
+
+        match self {
+            Self::Int | Self::Float => self.convert(arg),
+            Self::Len => len(arg),
+            Self::Push | Self::Insert => {
+                self.update(&mut arg, args.collect())?;
+                Ok(arg)
+            }
+            Self::Keys | Self::Values => self.entries(arg),
+        }
+    }
+
+    /// Whether the builtin returns its first argument with a change made to
+    /// it, which it can then make in place with [`update`](Self::update).
+    pub(crate) fn is_update(self) -> bool {
+        matches!(self, Self::Push | Self::Insert)
+    }
+
+    /// Makes the change to `target` that the builtin makes to its first
+    /// argument, given the rest of its arguments in `args`. A list or map is
+    /// only copied first if some other value still shares it. On failure
+    /// `target` is left as it was.
+    pub(crate) fn update(self, target: &mut Value, args: Vec<Value>) -> Result<(), RuntimeError> {
+        self.check_arity(args.len() + 1)?;
+
+        let mut args = args.into_iter();
+        match self {
+            Self::Push => push(target, args.next().unwrap()),
+            Self::Insert => insert(target, args.next().unwrap(), args.next().unwrap()),
+            _ => unreachable!("`{}` does not update its argument", self.name()),
+        }
+    }
+
+    /// The keys or the values of a map as a list, in key order.
+    fn entries(self, arg: Value) -> Result<Value, RuntimeError> {
+        let map = match arg {
+            Value::Map(map) => map,
+            arg => {
+                return Err(RuntimeErrorKind::TypeError(format!(
+                    "cannot take the {} of {}, it is not a map",
+                    self.name(),
+                    arg
+                ))
+                .into())
+            }
+        };
+        let items = match self {
+            Self::Keys => map.keys().cloned().map(Value::String).collect(),
+            _ => map.values().cloned().collect(),
+        };
+
+        Ok(Value::list(items))
+    }
+
+    fn convert(self, arg: Value) -> Result<Value, RuntimeError> {
+        match (self, arg) {
+            (Self::Int, Value::Number(n)) => Ok(Value::Number(n)),
+            // Truncates towards zero, like a cast would.
+            (Self::Int, Value::Float(x)) if x.is_finite() => {
+                let truncated = x.trunc();
+                // `i64::MAX as f64` rounds up to 2^63, which is already out of range.
+                if truncated < i64::MIN as f64 || truncated >= i64::MAX as f64 {
+                    #[cfg(feature = "bigint")]
+                    return Ok(bigint::from_f64(x)
+                        .map(Value::BigInt)
+                        .ok_or(RuntimeErrorKind::Overflow)?);
+                    #[cfg(not(feature = "bigint"))]
+                    return Err(RuntimeErrorKind::Overflow.into());
+                }
+
+                Ok(Value::Number(truncated as i64))
+            }
+            #[cfg(feature = "bigint")]
+            (Self::Int, Value::BigInt(n)) => Ok(Value::BigInt(n)),
+            #[cfg(feature = "bigint")]
+            (Self::Float, Value::BigInt(n)) => Ok(Value::Float(bigint::to_f64(&n))),
+            (Self::Float, Value::Number(n)) => Ok(Value::Float(n as f64)),
+            (Self::Float, Value::Float(x)) => Ok(Value::Float(x)),
+            (_, arg) => Err(RuntimeErrorKind::TypeError(format!(
+                "cannot convert {} with `{}`",
+                arg,
+                self.name()
+            ))
+            .into()),
+        }
+    }
+}
+
+/// The number of items in a list or map, or of characters in a string.
+fn len(arg: Value) -> Result<Value, RuntimeError> {
+    let len = match arg {
+        Value::List(list) => list.len(),
+        Value::Map(map) => map.len(),
+        Value::String(s) => s.chars().count(),
+        arg => {
+            return Err(
+                RuntimeErrorKind::TypeError(format!("cannot take the length of {}", arg)).into(),
+            )
+        }
+    };
+
+    Ok(Value::Number(len as i64))
+}
+
+/// Appends `item` to the list.
+fn push(list: &mut Value, item: Value) -> Result<(), RuntimeError> {
+    match list {
+        Value::List(list) => {
+            Rc::make_mut(list).push(item);
+            Ok(())
+        }
+        list => Err(RuntimeErrorKind::TypeError(format!(
+            "cannot push onto {}, it is not a list",
+            list
+        ))
+        .into()),
+    }
+}
+
+/// Adds or replaces the entry for `key`.
+fn insert(map: &mut Value, key: Value, value: Value) -> Result<(), RuntimeError> {
+    match (map, key) {
+        (Value::Map(map), Value::String(key)) => {
+            Rc::make_mut(map).insert(key, value);
+            Ok(())
+        }
+        (Value::Map(_), key) => Err(RuntimeErrorKind::TypeError(format!(
+            "map key must be a string, found {}",
+            key
+        ))
+        .into()),
+        (map, _) => Err(RuntimeErrorKind::TypeError(format!(
+            "cannot insert into {}, it is not a map",
+            map
+        ))
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn int_truncates_float() {
+        assert_eq!(
+            Builtin::Int.call(vec![Value::Float(-2.9)]),
+            Ok(Value::Number(-2))
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn int_of_float_out_of_range() {
+        assert_eq!(
+            Builtin::Int.call(vec![Value::Float(1e19)]),
+            Err(RuntimeErrorKind::Overflow.into()),
+        );
+    }
+
+    #[test]
+    fn int_of_nan() {
+        assert_eq!(
+            Builtin::Int.call(vec![Value::Float(f64::NAN)]),
+            Err(RuntimeErrorKind::TypeError("cannot convert NaN with `int`".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn float_of_number() {
+        assert_eq!(
+            Builtin::Float.call(vec![Value::Number(3)]),
+            Ok(Value::Float(3.0))
+        );
+    }
+
+    #[test]
+    fn float_of_bool() {
+        assert_eq!(
+            Builtin::Float.call(vec![Value::Bool(true)]),
+            Err(RuntimeErrorKind::TypeError("cannot convert true with `float`".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn builtin_with_wrong_arity() {
+        assert_eq!(
+            Builtin::Float.call(Vec::new()),
+            Err(RuntimeErrorKind::ArityMismatch {
+                name: "float".to_string(),
+                expected: 1,
+                found: 0,
+            }
+            .into()),
+        );
+    }
+
+    #[test]
+    fn len_of_list_and_string() {
+        assert_eq!(
+            Builtin::Len.call(vec![Value::list(vec![Value::Unit; 3])]),
+            Ok(Value::Number(3))
+        );
+        assert_eq!(
+            Builtin::Len.call(vec![Value::String("héllo".to_string())]),
+            Ok(Value::Number(5))
+        );
+    }
+
+    #[test]
+    fn push_onto_shared_list() {
+        let list = Value::list(Vec::new());
+
+        assert_eq!(
+            Builtin::Push.call(vec![list.clone(), Value::Number(1)]),
+            Ok(Value::list(vec![Value::Number(1)]))
+        );
+        assert_eq!(list, Value::list(Vec::new()));
+    }
+
+    #[test]
+    fn push_onto_non_list() {
+        assert_eq!(
+            Builtin::Push.call(vec![Value::Number(1), Value::Number(2)]),
+            Err(
+                RuntimeErrorKind::TypeError("cannot push onto 1, it is not a list".to_string())
+                    .into()
+            ),
+        );
+    }
+}
@@ -0,0 +1,207 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::env::Env;
+use crate::error::{ParseFailure, RuntimeError};
+use crate::expression::Expression;
+use crate::func_def::Captured;
+use crate::utils;
+use crate::value::Value;
+
+/// An anonymous function expression such as `|x| x + offset`.
+#[derive(Debug, PartialEq)]
+pub struct ClosureDef {
+    pub params: Vec<String>,
+    pub body: Rc<Expression>,
+}
+
+impl ClosureDef {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let (s, params) = utils::extract_params("|", "|", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, body) = Expression::new(s)?;
+
+        Ok((
+            s,
+            Self {
+                params,
+                body: Rc::new(body),
+            },
+        ))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Value {
+        Value::Closure(Rc::new(Closure {
+            params: self.params.clone(),
+            body: Rc::clone(&self.body),
+            captured: Captured::new(env),
+        }))
+    }
+}
+
+/// The value a `ClosureDef` evaluates to. It keeps a handle to the scope it
+/// was created in, so captured bindings stay alive and shared for as long as
+/// the closure does.
+pub struct Closure {
+    pub params: Vec<String>,
+    pub body: Rc<Expression>,
+    pub(crate) captured: Captured,
+}
+
+impl Closure {
+    pub(crate) fn call(&self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.captured
+            .call("<closure>", &self.params, args, |env| self.body.eval(env))
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Closure")
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body) && self.captured == other.captured
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding_usage::BindingUsage;
+    use crate::block::Block;
+    use crate::error::RuntimeErrorKind;
+    use crate::expression::Operator;
+
+    #[test]
+    fn parse_closure() {
+        assert_eq!(
+            ClosureDef::new("|x| x + offset"),
+            Ok((
+                "",
+                ClosureDef {
+                    params: vec!["x".to_string()],
+                    body: Rc::new(Expression::Operation {
+                        lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "x".to_string(),
+                        })),
+                        rhs: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "offset".to_string(),
+                        })),
+                        op: Operator::Add,
+                    }),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_closure_without_params() {
+        assert_eq!(
+            ClosureDef::new("|| {}"),
+            Ok((
+                "",
+                ClosureDef {
+                    params: Vec::new(),
+                    body: Rc::new(Expression::Block(Block {
+                        statements: Vec::new(),
+                        locations: Vec::new(),
+                    })),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_closure_with_duplicate_params() {
+        assert_eq!(
+            ClosureDef::new("|x, y, x| x"),
+            Err(ParseFailure::new("x| x", "a parameter name other than `x`")),
+        );
+    }
+
+    #[test]
+    fn parse_closure_as_argument() {
+        assert!(matches!(
+            Expression::new("map(|a, b| a * b, 2)"),
+            Ok(("", Expression::FuncCall(ref call))) if call.args.len() == 2,
+        ));
+    }
+
+    #[test]
+    fn eval_closure_capturing_binding() {
+        let (_, block) = Block::new(
+            "{
+                let offset = 10
+                let shift = |x| x + offset
+                shift(5)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(15)));
+    }
+
+    #[test]
+    fn eval_closure_outliving_its_scope() {
+        let (_, block) = Block::new(
+            "{
+                fn adder(n) { |x| x + n }
+                let add5 = adder(5)
+                let add7 = adder(7)
+                add5(1) * 100 + add7(1)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(608)));
+    }
+
+    #[test]
+    fn eval_closure_mutating_captured_binding() {
+        let (_, block) = Block::new(
+            "{
+                let counter = {
+                    let mut count = 0
+                    let increment = || {
+                        count += 1
+                        count
+                    }
+                    increment
+                }
+                counter()
+                counter()
+                counter()
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(3)));
+    }
+
+    #[test]
+    fn eval_closure_called_immediately() {
+        let (_, expression) = Expression::new("(|a, b| a - b)(10, 4)").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Number(6)));
+    }
+
+    #[test]
+    fn eval_closure_with_wrong_arity() {
+        let (_, expression) = Expression::new("(|a| a)()").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::ArityMismatch {
+                name: "<closure>".to_string(),
+                expected: 1,
+                found: 0,
+            }
+            .into()),
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use crate::error::{Location, RuntimeError, RuntimeErrorKind};
+use crate::value::Value;
+
+/// Reason why evaluation stopped before producing a value. Besides genuine
+/// errors this carries the non-local jumps of `break`, `continue` and
+/// `return`, which unwind through every enclosing expression until a loop or
+/// function call picks them up.
+#[derive(Debug, PartialEq)]
+pub(crate) enum ControlFlow {
+    Break(Value),
+    Continue,
+    Return(Value),
+    /// Boxed to keep results small: every level of evaluation passes them up
+    /// through several stack frames.
+    Error(Box<RuntimeError>),
+}
+
+impl ControlFlow {
+    /// Turns a jump that escaped every enclosing loop into an error.
+    pub(crate) fn into_runtime_error(self) -> RuntimeError {
+        match self {
+            Self::Break(_) => RuntimeErrorKind::BreakOutsideLoop.into(),
+            Self::Continue => RuntimeErrorKind::ContinueOutsideLoop.into(),
+            Self::Return(_) => RuntimeErrorKind::ReturnOutsideFunction.into(),
+            Self::Error(err) => *err,
+        }
+    }
+
+    /// Records the statement an error happened in; jumps pass unchanged.
+    pub(crate) fn with_location(self, location: Location) -> Self {
+        match self {
+            Self::Error(err) => Self::Error(Box::new(err.with_location(location))),
+            flow => flow,
+        }
+    }
+}
+
+impl From<RuntimeError> for ControlFlow {
+    fn from(err: RuntimeError) -> Self {
+        Self::Error(Box::new(err))
+    }
+}
+
+impl From<RuntimeErrorKind> for ControlFlow {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        Self::Error(Box::new(kind.into()))
+    }
+}
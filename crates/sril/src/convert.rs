@@ -0,0 +1,248 @@
+//! Conversions between [`Value`] and Rust types, used to pass data between
+//! scripts and the application embedding them.
+//!
+//! Converting into a `Value` cannot fail, so it is done with `From`. The other
+//! direction uses `TryFrom` and reports values of the wrong type as a
+//! [`RuntimeError`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+
+use crate::error::{RuntimeError, RuntimeErrorKind};
+use crate::value::Value;
+
+fn type_error(expected: &str, found: &Value) -> RuntimeError {
+    RuntimeErrorKind::TypeError(format!("expected {}, found {}", expected, found)).into()
+}
+
+// Lets `Value` itself, whose conversion from `Value` cannot fail, be used
+// wherever a conversion reporting a `RuntimeError` is expected.
+impl From<Infallible> for RuntimeError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+impl From<i32> for Value {
+    fn from(n: i32) -> Self {
+        Self::Number(n.into())
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Self::Number(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(x: f64) -> Self {
+        Self::Float(x)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Self::Bool(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Self::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Self::String(s.to_string())
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(items: Vec<T>) -> Self {
+        Self::list(items.into_iter().map(Into::into).collect())
+    }
+}
+
+impl<T: Into<Value>> From<HashMap<String, T>> for Value {
+    fn from(entries: HashMap<String, T>) -> Self {
+        Self::map(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    }
+}
+
+impl TryFrom<Value> for i64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Number(n) => Ok(n),
+            #[cfg(feature = "bigint")]
+            Value::BigInt(_) => Err(type_error("an integer that fits in an i64", &value)),
+            value => Err(type_error("an integer", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for i32 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let n = i64::try_from(value)?;
+        i32::try_from(n)
+            .map_err(|_| type_error("an integer that fits in an i32", &Value::Number(n)))
+    }
+}
+
+impl TryFrom<Value> for f64 {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Float(x) => Ok(x),
+            Value::Number(n) => Ok(n as f64),
+            value => Err(type_error("a number", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Bool(b) => Ok(b),
+            value => Err(type_error("a boolean", &value)),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(s),
+            value => Err(type_error("a string", &value)),
+        }
+    }
+}
+
+impl<T> TryFrom<Value> for Vec<T>
+where
+    T: TryFrom<Value>,
+    RuntimeError: From<T::Error>,
+{
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::List(items) => items
+                .iter()
+                .map(|item| Ok(T::try_from(item.clone())?))
+                .collect(),
+            value => Err(type_error("a list", &value)),
+        }
+    }
+}
+
+impl<T> TryFrom<Value> for HashMap<String, T>
+where
+    T: TryFrom<Value>,
+    RuntimeError: From<T::Error>,
+{
+    type Error = RuntimeError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Map(entries) => entries
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), T::try_from(value.clone())?)))
+                .collect(),
+            value => Err(type_error("a map", &value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn convert_scalars_to_values() {
+        assert_eq!(Value::from(7), Value::Number(7));
+        assert_eq!(Value::from(0.5), Value::Float(0.5));
+        assert_eq!(Value::from(true), Value::Bool(true));
+        assert_eq!(Value::from("hi"), Value::String("hi".to_string()));
+    }
+
+    #[test]
+    fn convert_collections_to_values() {
+        assert_eq!(
+            Value::from(vec![vec![1], vec![]]),
+            Value::list(vec![
+                Value::list(vec![Value::Number(1)]),
+                Value::list(vec![])
+            ]),
+        );
+        assert_eq!(
+            Value::from(HashMap::from([("a".to_string(), true)])),
+            Value::map(BTreeMap::from([("a".to_string(), Value::Bool(true))])),
+        );
+    }
+
+    #[test]
+    fn convert_values_to_scalars() {
+        assert_eq!(i32::try_from(Value::Number(-3)), Ok(-3));
+        assert_eq!(f64::try_from(Value::Number(2)), Ok(2.0));
+        assert_eq!(bool::try_from(Value::Bool(false)), Ok(false));
+        assert_eq!(
+            String::try_from(Value::String("x".to_string())),
+            Ok("x".to_string()),
+        );
+    }
+
+    #[test]
+    fn convert_values_to_collections() {
+        let list = Value::from(vec![1, 2]);
+        assert_eq!(Vec::<i32>::try_from(list.clone()), Ok(vec![1, 2]));
+        assert_eq!(
+            Vec::<Value>::try_from(list),
+            Ok(vec![Value::Number(1), Value::Number(2)]),
+        );
+
+        let map = Value::from(HashMap::from([("a".to_string(), "b")]));
+        assert_eq!(
+            HashMap::<String, String>::try_from(map),
+            Ok(HashMap::from([("a".to_string(), "b".to_string())])),
+        );
+    }
+
+    #[test]
+    fn cannot_convert_value_of_wrong_type() {
+        assert_eq!(
+            bool::try_from(Value::Number(1)),
+            Err(RuntimeErrorKind::TypeError("expected a boolean, found 1".to_string()).into()),
+        );
+        assert_eq!(
+            Vec::<i32>::try_from(Value::from(vec!["a"])),
+            Err(RuntimeErrorKind::TypeError("expected an integer, found \"a\"".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn cannot_convert_integer_out_of_range() {
+        assert_eq!(
+            i32::try_from(Value::Number(1 << 40)),
+            Err(RuntimeErrorKind::TypeError(
+                "expected an integer that fits in an i32, found 1099511627776".to_string()
+            )
+            .into()),
+        );
+    }
+}
@@ -1,34 +1,895 @@
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
 
+use crate::binding_def::BindingKind;
+use crate::control_flow::ControlFlow;
+use crate::error::{RuntimeError, RuntimeErrorKind, Source};
 use crate::value::Value;
 
-#[derive(Debug, PartialEq, Default)]
-pub struct Env<'parent> {
-    bindings: HashMap<String, Value>,
-    parent: Option<&'parent Self>,
+#[derive(Debug, PartialEq, Clone)]
+struct Binding {
+    value: Value,
+    kind: BindingKind,
 }
 
-impl<'parent> Env<'parent> {
-    pub(crate) fn create_child(&'parent self) -> Self {
+/// Index of a scope in the arena of its [`Root`].
+type ScopeId = usize;
+
+/// Number of live scopes at which unreachable cycles between them
+/// are first looked for, see [`Root::collect_cycles`].
+const MIN_COLLECT_AT: usize = 1024;
+
+/// Number of nested function calls allowed before evaluation is aborted,
+/// unless configured otherwise with [`Env::set_max_call_depth`]. A call to a
+/// small function takes about six levels of evaluation, so recursion runs into
+/// this limit well before [`DEFAULT_MAX_EVAL_DEPTH`].
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 4_000;
+
+/// Number of expressions and blocks that may be nested inside each other,
+/// counting those in the bodies of the functions being called, unless
+/// configured otherwise with [`Env::set_max_eval_depth`]. The stack is grown
+/// on the heap as evaluation goes deeper, whatever the stack of the host
+/// thread. Each level takes up to about 2.5 KiB of it in a debug build and a
+/// third of that in a release build, so this bounds it to about 75 MiB.
+pub const DEFAULT_MAX_EVAL_DEPTH: usize = 30_000;
+
+/// State shared by a root scope and all of its descendants.
+#[derive(Debug)]
+struct Root {
+    call_depth: Cell<usize>,
+    max_call_depth: Cell<usize>,
+    eval_depth: Cell<usize>,
+    max_eval_depth: Cell<usize>,
+    /// The source text of the code being evaluated, if it is known.
+    source: RefCell<Option<Arc<Source>>>,
+    /// Every scope under this root. Scopes, functions and closures refer to a
+    /// scope by its index here instead of owning it, so dropping the root
+    /// frees all of them, even those that refer to each other in a cycle.
+    scopes: RefCell<Vec<Slot>>,
+    /// Slots whose scope has been freed, to be reused.
+    free: RefCell<Vec<ScopeId>>,
+    /// Freed scopes waiting to be dropped. Dropping one can free others, which
+    /// are queued here rather than dropped recursively so that a long chain
+    /// of scopes cannot overflow the stack.
+    released: RefCell<Vec<Scope>>,
+    dropping_released: Cell<bool>,
+    live: Cell<usize>,
+    collect_at: Cell<usize>,
+}
+
+impl Default for Root {
+    fn default() -> Self {
+        Self {
+            call_depth: Cell::new(0),
+            max_call_depth: Cell::new(DEFAULT_MAX_CALL_DEPTH),
+            eval_depth: Cell::new(0),
+            max_eval_depth: Cell::new(DEFAULT_MAX_EVAL_DEPTH),
+            source: RefCell::new(None),
+            scopes: RefCell::new(Vec::new()),
+            free: RefCell::new(Vec::new()),
+            released: RefCell::new(Vec::new()),
+            dropping_released: Cell::new(false),
+            live: Cell::new(0),
+            collect_at: Cell::new(MIN_COLLECT_AT),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Slot {
+    scope: Option<Scope>,
+    /// Number of [`Env`]s and [`ScopeRef`]s referring to the scope.
+    handles: usize,
+}
+
+impl Slot {
+    fn scope(&self) -> &Scope {
+        self.scope
+            .as_ref()
+            .expect("a scope is only freed once nothing refers to it")
+    }
+
+    fn scope_mut(&mut self) -> &mut Scope {
+        self.scope
+            .as_mut()
+            .expect("a scope is only freed once nothing refers to it")
+    }
+}
+
+impl Root {
+    /// Adds `scope` to the arena, first collecting unreachable cycles if the
+    /// number of live scopes has doubled since they last were.
+    fn allocate(&self, scope: Scope) -> ScopeId {
+        if self.live.get() >= self.collect_at.get() {
+            self.collect_cycles();
+            self.collect_at
+                .set((2 * self.live.get()).max(MIN_COLLECT_AT));
+        }
+        self.live.set(self.live.get() + 1);
+
+        let slot = Slot {
+            scope: Some(scope),
+            handles: 1,
+        };
+        let mut scopes = self.scopes.borrow_mut();
+
+        match self.free.borrow_mut().pop() {
+            Some(id) => {
+                scopes[id] = slot;
+                id
+            }
+            None => {
+                scopes.push(slot);
+                scopes.len() - 1
+            }
+        }
+    }
+
+    fn retain(&self, id: ScopeId) {
+        let mut scopes = self.scopes.borrow_mut();
+        debug_assert!(scopes[id].scope.is_some(), "retained a freed scope");
+        scopes[id].handles += 1;
+    }
+
+    fn release(&self, id: ScopeId) {
+        let scope = {
+            let mut scopes = self.scopes.borrow_mut();
+            let slot = &mut scopes[id];
+
+            // The scope may already have been freed as part of a cycle, which
+            // leaves only the handles inside that cycle to be dropped.
+            if slot.scope.is_none() {
+                return;
+            }
+
+            slot.handles -= 1;
+            if slot.handles > 0 {
+                return;
+            }
+
+            self.free.borrow_mut().push(id);
+            self.live.set(self.live.get() - 1);
+            slot.scope.take()
+        };
+
+        self.released.borrow_mut().extend(scope);
+        if self.dropping_released.replace(true) {
+            return;
+        }
+
+        // Nothing may be borrowed while a scope is dropped, since that
+        // releases the scopes it refers to, which are queued in turn.
+        loop {
+            let scope = self.released.borrow_mut().pop();
+            match scope {
+                Some(scope) => drop(scope),
+                None => break,
+            }
+        }
+        self.dropping_released.set(false);
+    }
+
+    /// Frees the scopes that are only kept alive by reference cycles, like a
+    /// function stored in the scope it was defined in, or a closure stored in
+    /// a list in a scope enclosing the one it captures.
+    ///
+    /// The references from within the arena are counted first: between
+    /// scopes, from bindings to the lists, maps, functions and closures they
+    /// hold, and from those on to what they hold in turn. Anything with more
+    /// references than that is also referred to from outside the arena, from
+    /// an [`Env`] or a value the host or the evaluator holds on to. Whatever
+    /// can be reached from those is in use, and the remaining scopes are
+    /// garbage.
+    fn collect_cycles(&self) {
+        let garbage: Vec<ScopeId> = {
+            let scopes = self.scopes.borrow();
+            let mut graph = Graph::new(self, &scopes);
+            graph.count_internal_references();
+            graph.unreachable_scopes()
+        };
+
+        let garbage: Vec<Scope> = {
+            let mut scopes = self.scopes.borrow_mut();
+            self.free.borrow_mut().extend(&garbage);
+            self.live.set(self.live.get() - garbage.len());
+
+            garbage
+                .into_iter()
+                .filter_map(|id| {
+                    scopes[id].handles = 0;
+                    scopes[id].scope.take()
+                })
+                .collect()
+        };
+
+        drop(garbage);
+    }
+}
+
+/// Something a scope or value refers to, see [`Root::collect_cycles`].
+enum Reference<'a> {
+    Scope(ScopeId),
+    Value(&'a Value),
+}
+
+/// The references between the scopes of a root and the shared values in
+/// them, while looking for unreachable cycles.
+struct Graph<'a> {
+    root: &'a Root,
+    scopes: &'a [Slot],
+    internal_scope_refs: Vec<usize>,
+    /// The shared values found in the scopes, with the number of references
+    /// to them from within the arena.
+    values: HashMap<*const (), (&'a Value, usize)>,
+}
+
+impl<'a> Graph<'a> {
+    fn new(root: &'a Root, scopes: &'a [Slot]) -> Self {
+        Self {
+            root,
+            scopes,
+            internal_scope_refs: vec![0; scopes.len()],
+            values: HashMap::new(),
+        }
+    }
+
+    fn count_internal_references(&mut self) {
+        let mut unvisited = Vec::new();
+
+        let scopes = self.scopes;
+        for scope in scopes.iter().filter_map(|slot| slot.scope.as_ref()) {
+            for reference in scope_references(scope) {
+                self.count(reference, &mut unvisited);
+            }
+        }
+
+        while let Some(value) = unvisited.pop() {
+            for reference in self.value_references(value) {
+                self.count(reference, &mut unvisited);
+            }
+        }
+    }
+
+    /// Counts one reference from within the arena, adding a shared value seen
+    /// for the first time to the ones whose references are still to count.
+    fn count(&mut self, reference: Reference<'a>, unvisited: &mut Vec<&'a Value>) {
+        match reference {
+            Reference::Scope(id) => self.internal_scope_refs[id] += 1,
+            Reference::Value(value) => {
+                if let Some((ptr, _)) = shared(value) {
+                    let (_, refs) = self.values.entry(ptr).or_insert((value, 0));
+                    *refs += 1;
+                    if *refs == 1 {
+                        unvisited.push(value);
+                    }
+                }
+            }
+        }
+    }
+
+    fn unreachable_scopes(&self) -> Vec<ScopeId> {
+        let mut reachable_scopes = vec![false; self.scopes.len()];
+        let mut reachable_values = HashSet::new();
+
+        let mut unvisited: Vec<Reference<'a>> = self
+            .scopes
+            .iter()
+            .enumerate()
+            .filter(|(id, slot)| {
+                slot.scope.is_some() && slot.handles > self.internal_scope_refs[*id]
+            })
+            .map(|(id, _)| Reference::Scope(id))
+            .chain(
+                self.values
+                    .values()
+                    .filter(|(value, refs)| matches!(shared(value), Some((_, strong)) if strong > *refs))
+                    .map(|(value, _)| Reference::Value(value)),
+            )
+            .collect();
+
+        while let Some(reference) = unvisited.pop() {
+            match reference {
+                Reference::Scope(id) => {
+                    if !reachable_scopes[id] {
+                        reachable_scopes[id] = true;
+                        unvisited.extend(scope_references(self.scopes[id].scope()));
+                    }
+                }
+                Reference::Value(value) => {
+                    if let Some((ptr, _)) = shared(value) {
+                        if reachable_values.insert(ptr) {
+                            unvisited.extend(self.value_references(value));
+                        }
+                    }
+                }
+            }
+        }
+
+        self.scopes
+            .iter()
+            .enumerate()
+            .filter(|(id, slot)| slot.scope.is_some() && !reachable_scopes[*id])
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// What `value` refers to directly. A function or closure defined under
+    /// another root refers to nothing in this arena.
+    fn value_references(&self, value: &'a Value) -> Vec<Reference<'a>> {
+        match value {
+            Value::List(list) => list.iter().map(Reference::Value).collect(),
+            Value::Map(map) => map.values().map(Reference::Value).collect(),
+            Value::Function(function) => self.captured(function.captured.scope()),
+            Value::Closure(closure) => self.captured(closure.captured.scope()),
+            _ => Vec::new(),
+        }
+    }
+
+    fn captured(&self, scope: &ScopeRef) -> Vec<Reference<'a>> {
+        if std::ptr::eq(scope.root.as_ptr(), self.root) {
+            vec![Reference::Scope(scope.id)]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn scope_references(scope: &Scope) -> impl Iterator<Item = Reference<'_>> {
+    scope
+        .parent
+        .iter()
+        .map(|parent| Reference::Scope(parent.id))
+        .chain(
+            scope
+                .bindings
+                .values()
+                .map(|binding| Reference::Value(&binding.value)),
+        )
+}
+
+/// The allocation behind a value that can refer to scopes, along with the
+/// number of references to it.
+fn shared(value: &Value) -> Option<(*const (), usize)> {
+    match value {
+        Value::List(list) => Some((Rc::as_ptr(list).cast(), Rc::strong_count(list))),
+        Value::Map(map) => Some((Rc::as_ptr(map).cast(), Rc::strong_count(map))),
+        Value::Function(function) => {
+            Some((Rc::as_ptr(function).cast(), Rc::strong_count(function)))
+        }
+        Value::Closure(closure) => Some((Rc::as_ptr(closure).cast(), Rc::strong_count(closure))),
+        _ => None,
+    }
+}
+
+/// Marks one active function call; the call depth goes back down when it is
+/// dropped, however the call ends.
+pub(crate) struct CallGuard(Rc<Root>);
+
+impl Drop for CallGuard {
+    fn drop(&mut self) {
+        self.0.call_depth.set(self.0.call_depth.get() - 1);
+    }
+}
+
+/// Marks one expression or block being evaluated, like [`CallGuard`] does
+/// for function calls.
+pub(crate) struct EvalGuard(Rc<Root>);
+
+impl Drop for EvalGuard {
+    fn drop(&mut self) {
+        self.0.eval_depth.set(self.0.eval_depth.get() - 1);
+    }
+}
+
+/// Restores the source that was being evaluated before, once the code that
+/// replaced it is done.
+pub(crate) struct SourceGuard {
+    root: Rc<Root>,
+    previous: Option<Arc<Source>>,
+}
+
+impl Drop for SourceGuard {
+    fn drop(&mut self) {
+        *self.root.source.borrow_mut() = self.previous.take();
+    }
+}
+
+#[derive(Debug)]
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    parent: Option<ScopeRef>,
+}
+
+/// A reference to a scope that, unlike an [`Env`], does not keep the root of
+/// the scope alive. Scopes refer to their parent with one, and functions and
+/// closures to the scope they were defined in.
+#[derive(Debug)]
+pub(crate) struct ScopeRef {
+    root: Weak<Root>,
+    id: ScopeId,
+}
+
+impl ScopeRef {
+    /// A handle to the scope, unless its root has been dropped since.
+    pub(crate) fn env(&self) -> Option<Env> {
+        let root = self.root.upgrade()?;
+        root.retain(self.id);
+        Some(Env { root, id: self.id })
+    }
+
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Weak::ptr_eq(&self.root, &other.root) && self.id == other.id
+    }
+}
+
+impl Clone for ScopeRef {
+    fn clone(&self) -> Self {
+        if let Some(root) = self.root.upgrade() {
+            root.retain(self.id);
+        }
+
         Self {
+            root: Weak::clone(&self.root),
+            id: self.id,
+        }
+    }
+}
+
+impl Drop for ScopeRef {
+    fn drop(&mut self) {
+        if let Some(root) = self.root.upgrade() {
+            root.release(self.id);
+        }
+    }
+}
+
+/// A handle to a scope of bindings. Cloning an `Env` yields another handle to
+/// the same scope, so a child scope can outlive the code that created it and
+/// still read and update the bindings of every scope enclosing it.
+///
+/// The scopes themselves belong to the root scope, which lives as long as any
+/// `Env` under it does. Once the last one is dropped, every scope under the
+/// root is freed along with the functions and closures defined in them.
+pub struct Env {
+    root: Rc<Root>,
+    id: ScopeId,
+}
+
+impl Default for Env {
+    fn default() -> Self {
+        let root = Rc::new(Root::default());
+        let id = root.allocate(Scope {
+            bindings: HashMap::new(),
+            parent: None,
+        });
+
+        Self { root, id }
+    }
+}
+
+impl Clone for Env {
+    fn clone(&self) -> Self {
+        self.root.retain(self.id);
+
+        Self {
+            root: Rc::clone(&self.root),
+            id: self.id,
+        }
+    }
+}
+
+impl Drop for Env {
+    fn drop(&mut self) {
+        self.root.release(self.id);
+    }
+}
+
+impl fmt::Debug for Env {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Env")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Env {
+    pub(crate) fn create_child(&self) -> Self {
+        let id = self.root.allocate(Scope {
             bindings: HashMap::new(),
-            parent: Some(self),
+            parent: Some(self.capture()),
+        });
+
+        Self {
+            root: Rc::clone(&self.root),
+            id,
+        }
+    }
+
+    /// A reference to this scope for a function or closure defined in it.
+    pub(crate) fn capture(&self) -> ScopeRef {
+        self.root.retain(self.id);
+
+        ScopeRef {
+            root: Rc::downgrade(&self.root),
+            id: self.id,
         }
     }
 
-    pub(crate) fn store_binding(&mut self, name: String, value: Value) {
-        self.bindings.insert(name, value);
+    pub fn max_call_depth(&self) -> usize {
+        self.root.max_call_depth.get()
     }
 
-    pub(crate) fn get_binding_value(&self, name: &str) -> Result<Value, String> {
+    /// Sets how deeply function calls may nest, for this scope and every scope
+    /// sharing its root. Exceeding the limit is reported as a runtime error
+    /// instead of overflowing the stack of the host.
+    pub fn set_max_call_depth(&self, max: usize) {
+        self.root.max_call_depth.set(max);
+    }
+
+    pub(crate) fn enter_call(&self) -> Result<CallGuard, RuntimeError> {
+        let root = Rc::clone(&self.root);
+        let max = root.max_call_depth.get();
+
+        if root.call_depth.get() >= max {
+            return Err(RuntimeErrorKind::StackOverflow { max_depth: max }.into());
+        }
+
+        root.call_depth.set(root.call_depth.get() + 1);
+        Ok(CallGuard(root))
+    }
+
+    pub fn max_eval_depth(&self) -> usize {
+        self.root.max_eval_depth.get()
+    }
+
+    /// Sets how deeply expressions and blocks may nest during evaluation.
+    /// Counting function calls alone does not bound the stack a script uses,
+    /// since a single call can nest any number of blocks, loops and closures.
+    /// The stack grows as needed, so raising this limit along with the call
+    /// depth only lets deep recursion take more memory.
+    pub fn set_max_eval_depth(&self, max: usize) {
+        self.root.max_eval_depth.set(max);
+    }
+
+    pub(crate) fn enter_eval(&self) -> Result<EvalGuard, ControlFlow> {
+        let root = Rc::clone(&self.root);
+        let max = root.max_eval_depth.get();
+
+        if root.eval_depth.get() >= max {
+            return Err(RuntimeErrorKind::NestingTooDeep { max_depth: max }.into());
+        }
+
+        root.eval_depth.set(root.eval_depth.get() + 1);
+        Ok(EvalGuard(root))
+    }
+
+    /// The source text of the code being evaluated, which functions defined
+    /// by that code keep to report where their errors happen.
+    pub(crate) fn source(&self) -> Option<Arc<Source>> {
+        self.root.source.borrow().clone()
+    }
+
+    /// Makes `source` the code being evaluated until the guard is dropped.
+    pub(crate) fn enter_source(&self, source: Option<Arc<Source>>) -> SourceGuard {
+        let root = Rc::clone(&self.root);
+        let previous = root.source.replace(source);
+        SourceGuard { root, previous }
+    }
+
+    pub(crate) fn store_binding(
+        &self,
+        name: String,
+        value: Value,
+        kind: BindingKind,
+    ) -> Result<(), RuntimeError> {
+        let mut scopes = self.root.scopes.borrow_mut();
+        let bindings = &mut scopes[self.id].scope_mut().bindings;
+
+        if let Some(Binding {
+            kind: BindingKind::Const,
+            ..
+        }) = bindings.get(&name)
+        {
+            return Err(RuntimeErrorKind::ConstRedefinition(name).into());
+        }
+
+        let shadowed = bindings.insert(name, Binding { value, kind });
+
+        // The shadowed value may hold the last reference to a scope, which
+        // needs the arena to be free again to be released.
+        drop(scopes);
+        drop(shadowed);
+        Ok(())
+    }
+
+    /// Updates the nearest binding called `name`, looking through the
+    /// enclosing scopes if the current one does not define it.
+    pub(crate) fn assign_binding(&self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        self.replace_binding(name, value).map(drop)
+    }
+
+    /// Like [`assign_binding`](Self::assign_binding), but returns the value
+    /// the binding had.
+    pub(crate) fn replace_binding(&self, name: &str, value: Value) -> Result<Value, RuntimeError> {
+        let mut scopes = self.root.scopes.borrow_mut();
+        let mut id = self.id;
+
+        let previous = loop {
+            let scope = scopes[id].scope_mut();
+
+            match scope.bindings.get_mut(name) {
+                Some(binding) if binding.kind.is_mutable() => {
+                    break std::mem::replace(&mut binding.value, value);
+                }
+                Some(_) => return Err(RuntimeErrorKind::AssignToImmutable(name.to_string()).into()),
+                None => match &scope.parent {
+                    Some(parent) => id = parent.id,
+                    None => return Err(RuntimeErrorKind::UndefinedBinding(name.to_string()).into()),
+                },
+            }
+        };
+
+        // Like a shadowed value in `store_binding`, the previous value must
+        // only be dropped once the arena is no longer borrowed.
+        drop(scopes);
+        Ok(previous)
+    }
+
+    pub(crate) fn get_binding_value(&self, name: &str) -> Result<Value, RuntimeError> {
         self.get_binding_value_without_error_msg(name)
-            .ok_or_else(|| format!("binding with name ‘{}’ does not exist", name))
+            .ok_or_else(|| RuntimeErrorKind::UndefinedBinding(name.to_string()).into())
+    }
+
+    /// The bindings defined directly in this scope, sorted by name.
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        let mut bindings: Vec<_> = self.root.scopes.borrow()[self.id]
+            .scope()
+            .bindings
+            .iter()
+            .map(|(name, binding)| (name.clone(), binding.value.clone()))
+            .collect();
+        bindings.sort_by(|(a, _), (b, _)| a.cmp(b));
+        bindings
     }
 
     pub fn get_binding_value_without_error_msg(&self, name: &str) -> Option<Value> {
-        self.bindings.get(name).cloned().or_else(|| {
-            self.parent
-                .and_then(|parent| parent.get_binding_value_without_error_msg(name))
-        })
+        let scopes = self.root.scopes.borrow();
+        let mut id = self.id;
+
+        loop {
+            let scope = scopes[id].scope();
+
+            match scope.bindings.get(name) {
+                Some(binding) => return Some(binding.value.clone()),
+                None => id = scope.parent.as_ref()?.id,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_bindings_of_own_scope() {
+        let env = Env::default();
+        env.store_binding("y".to_string(), Value::Number(2), BindingKind::Let)
+            .unwrap();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::Const)
+            .unwrap();
+
+        let child = env.create_child();
+        child
+            .store_binding("z".to_string(), Value::Number(3), BindingKind::Let)
+            .unwrap();
+
+        assert_eq!(
+            env.bindings(),
+            vec![
+                ("x".to_string(), Value::Number(1)),
+                ("y".to_string(), Value::Number(2)),
+            ],
+        );
+        assert_eq!(child.bindings(), vec![("z".to_string(), Value::Number(3))]);
+    }
+
+    #[test]
+    fn assign_mutable_binding() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::LetMut)
+            .unwrap();
+
+        assert_eq!(env.assign_binding("x", Value::Number(2)), Ok(()));
+        assert_eq!(env.get_binding_value("x"), Ok(Value::Number(2)));
+    }
+
+    #[test]
+    fn cannot_assign_immutable_binding() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::Let)
+            .unwrap();
+
+        assert_eq!(
+            env.assign_binding("x", Value::Number(2)),
+            Err(RuntimeErrorKind::AssignToImmutable("x".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn cannot_assign_undefined_binding() {
+        assert_eq!(
+            Env::default().assign_binding("x", Value::Number(2)),
+            Err(RuntimeErrorKind::UndefinedBinding("x".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn shadow_let_binding() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::Let)
+            .unwrap();
+
+        assert_eq!(
+            env.store_binding("x".to_string(), Value::Bool(true), BindingKind::Let),
+            Ok(()),
+        );
+        assert_eq!(env.get_binding_value("x"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn shadow_const_binding_in_child_scope() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::Const)
+            .unwrap();
+
+        let child = env.create_child();
+        assert_eq!(
+            child.store_binding("x".to_string(), Value::Number(2), BindingKind::Let),
+            Ok(()),
+        );
+    }
+
+    #[test]
+    fn assign_binding_of_enclosing_scope() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::LetMut)
+            .unwrap();
+
+        let grandchild = env.create_child().create_child();
+        assert_eq!(grandchild.assign_binding("x", Value::Number(2)), Ok(()));
+        assert_eq!(env.get_binding_value("x"), Ok(Value::Number(2)));
+    }
+
+    #[test]
+    fn assign_nearest_shadowing_binding() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::LetMut)
+            .unwrap();
+
+        let child = env.create_child();
+        child
+            .store_binding("x".to_string(), Value::Number(10), BindingKind::LetMut)
+            .unwrap();
+        child.assign_binding("x", Value::Number(20)).unwrap();
+
+        assert_eq!(child.get_binding_value("x"), Ok(Value::Number(20)));
+        assert_eq!(env.get_binding_value("x"), Ok(Value::Number(1)));
+    }
+
+    #[test]
+    fn cannot_assign_immutable_binding_of_enclosing_scope() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(1), BindingKind::Let)
+            .unwrap();
+
+        assert_eq!(
+            env.create_child().assign_binding("x", Value::Number(2)),
+            Err(RuntimeErrorKind::AssignToImmutable("x".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn free_env_holding_functions() {
+        let env = Env::default();
+        let root = Rc::downgrade(&env.root);
+
+        crate::parse_program(
+            "fn f(x) { x }
+            fn g(x) {
+                fn h(y) { y * 2 }
+                let k = |y| h(y) + x
+                k(1)
+            }
+            let add = |x| f(x) + 1
+            f(1) + g(2) + add(3)",
+        )
+        .unwrap()
+        .eval(&env)
+        .unwrap();
+        let add = match env.get_binding_value("add") {
+            Ok(Value::Closure(add)) => Rc::downgrade(&add),
+            value => panic!("unexpected value: {:?}", value),
+        };
+
+        drop(env);
+        assert!(root.upgrade().is_none());
+        assert!(add.upgrade().is_none());
+    }
+
+    #[test]
+    fn collect_unreachable_cycles_while_running() {
+        let env = Env::default();
+        crate::parse_program(
+            "fn make() { fn inner() { 1 }; inner }
+            let mut last = make
+            for i in 0..5000 {
+                let f = make()
+                last = || f() + i
+            }",
+        )
+        .unwrap()
+        .eval(&env)
+        .unwrap();
+
+        assert!(env.root.live.get() <= MIN_COLLECT_AT + 1);
+    }
+
+    #[test]
+    fn keep_cycle_referred_to_from_outside_when_collecting() {
+        let env = Env::default();
+        let inner =
+            crate::parse_program("fn make() { let n = 1; fn inner() { n }; inner }\nmake()")
+                .unwrap()
+                .eval(&env)
+                .unwrap();
+
+        env.root.collect_cycles();
+        env.store_binding("f".to_string(), inner, BindingKind::Let)
+            .unwrap();
+
+        assert_eq!(
+            crate::parse_program("f()").unwrap().eval(&env),
+            Ok(Value::Number(1)),
+        );
+    }
+
+    #[test]
+    fn keep_reachable_scopes_when_collecting() {
+        let env = Env::default();
+
+        assert_eq!(
+            crate::parse_program(
+                "fn adder(n) { |x| x + n }
+                let mut fs = []
+                let mut sum = 0
+                for i in 0..3000 {
+                    fs = push(fs, |x| x + i)
+                    sum += adder(i)(1)
+                }
+                fs[0](1) + fs[2999](1) + sum",
+            )
+            .unwrap()
+            .eval(&env),
+            Ok(Value::Number(1 + 3000 + 4_501_500)),
+        );
+        assert!(env.root.live.get() > 3000);
+    }
+
+    #[test]
+    fn keep_scope_of_function_read_from_its_binding() {
+        let env = Env::default();
+        crate::parse_program("fn make() { fn inner() { 42 }; inner }\nlet f = make()")
+            .unwrap()
+            .eval(&env)
+            .unwrap();
+
+        assert_eq!(
+            crate::parse_program("f()").unwrap().eval(&env),
+            Ok(Value::Number(42)),
+        );
     }
 }
@@ -0,0 +1,444 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// A region of the source text. `start` and `end` are byte offsets, `line` and
+/// `column` are the 1-based position of `start`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub(crate) fn new(source: &str, start: usize, end: usize) -> Self {
+        let before = &source[..start];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        let column = before[line_start..].chars().count() + 1;
+
+        Self {
+            start,
+            end,
+            line,
+            column,
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Code that was parsed, kept along with the values defined by it so that a
+/// runtime error can be shown against the code it happened in.
+#[derive(Debug, PartialEq)]
+pub struct Source {
+    /// Where the code came from, such as a path, if it was given a name.
+    pub name: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    Parse(ParseError),
+    Runtime(RuntimeError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "parse error: {}", err),
+            Self::Runtime(err) => write!(f, "runtime error: {}", err),
+        }
+    }
+}
+
+impl Error {
+    /// The part of the source the error points at, if it is known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Parse(err) => Some(err.span),
+            Self::Runtime(err) => err.span,
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl From<RuntimeError> for Error {
+    fn from(err: RuntimeError) -> Self {
+        Self::Runtime(err)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    pub span: Span,
+    pub expected: Vec<String>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expected.as_slice() {
+            [] => write!(f, "unexpected input at {}", self.span),
+            [expected] => write!(f, "expected {} at {}", expected, self.span),
+            expected => write!(
+                f,
+                "expected one of {} at {}",
+                expected.join(", "),
+                self.span
+            ),
+        }
+    }
+}
+
+/// Failure reported by an individual parser. Parsers only ever see the
+/// unparsed remainder of the input, so the position is kept as the length of
+/// that remainder and only turned into a [`Span`] once the whole source is
+/// known.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseFailure {
+    remaining: usize,
+    expected: Vec<String>,
+    /// Whether the input is rejected at this position whatever else could
+    /// have come there, so that no other alternative is worth reporting.
+    conclusive: bool,
+}
+
+impl ParseFailure {
+    pub(crate) fn new(s: &str, expected: impl Into<String>) -> Self {
+        Self {
+            remaining: s.len(),
+            expected: vec![expected.into()],
+            conclusive: false,
+        }
+    }
+
+    /// The failure for input nested too deeply at `s` to be parsed at all.
+    pub(crate) fn nested_too_deeply(s: &str) -> Self {
+        Self {
+            conclusive: true,
+            ..Self::new(s, "less deeply nested code")
+        }
+    }
+
+    /// Combines the failures of two alternatives, keeping the one that got
+    /// further into the input and merging what they expected on a tie.
+    pub(crate) fn or(mut self, other: Self) -> Self {
+        if other.remaining < self.remaining {
+            return other;
+        }
+
+        if other.remaining == self.remaining && !self.conclusive {
+            if other.conclusive {
+                return other;
+            }
+
+            for expected in other.expected {
+                if !self.expected.contains(&expected) {
+                    self.expected.push(expected);
+                }
+            }
+        }
+
+        self
+    }
+
+    pub(crate) fn into_parse_error(self, source: &str) -> ParseError {
+        let start = source.len() - self.remaining;
+        let end = source[start..]
+            .chars()
+            .next()
+            .map(|c| start + c.len_utf8())
+            .unwrap_or(start);
+
+        ParseError {
+            span: Span::new(source, start, end),
+            expected: self.expected,
+        }
+    }
+}
+
+/// Position of a statement inside a block. Like the position of a
+/// [`ParseFailure`] it is kept as lengths of the remaining input, and only
+/// turned into a [`Span`] once an error is reported against the whole source.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) struct Location {
+    remaining_at_start: usize,
+    remaining_at_end: usize,
+}
+
+impl Location {
+    /// The location of the text between `start` and `end`, two remainders of
+    /// the same input.
+    pub(crate) fn new(start: &str, end: &str) -> Self {
+        Self {
+            remaining_at_start: start.len(),
+            remaining_at_end: end.len(),
+        }
+    }
+
+    fn into_span(self, source: &str) -> Span {
+        Span::new(
+            source,
+            source.len() - self.remaining_at_start,
+            source.len() - self.remaining_at_end,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeError {
+    pub kind: RuntimeErrorKind,
+    pub span: Option<Span>,
+    /// The code `span` points into. An error in the body of a function points
+    /// into the code the function was defined in, which need not be the code
+    /// that called it.
+    pub source: Option<Arc<Source>>,
+    /// The innermost statement that failed, until it is resolved into `span`.
+    pub(crate) location: Option<Location>,
+}
+
+impl RuntimeError {
+    pub(crate) fn with_span(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// Points the error at `span` within `source`, unless it already points
+    /// somewhere.
+    pub(crate) fn with_span_in(mut self, span: Span, source: &Arc<Source>) -> Self {
+        if self.span.is_none() {
+            self.source = Some(Arc::clone(source));
+        }
+        self.with_span(span)
+    }
+
+    /// Records the statement the error happened in, unless a statement nested
+    /// inside it already has been.
+    pub(crate) fn with_location(mut self, location: Location) -> Self {
+        if self.span.is_none() {
+            self.location.get_or_insert(location);
+        }
+        self
+    }
+
+    /// Turns the recorded location into a span within `source`, the code the
+    /// failing statement was parsed from. If that code is not known the
+    /// location is dropped rather than resolved against the wrong source.
+    pub(crate) fn resolve_location(mut self, source: Option<&Arc<Source>>) -> Self {
+        match (self.location.take(), source) {
+            (Some(location), Some(source)) => {
+                self.with_span_in(location.into_span(&source.text), source)
+            }
+            _ => self,
+        }
+    }
+}
+
+/// The location is only a stand-in for the span and the source only gives it
+/// context, so both are left out.
+impl PartialEq for RuntimeError {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.span == other.span
+    }
+}
+
+impl From<RuntimeErrorKind> for RuntimeError {
+    fn from(kind: RuntimeErrorKind) -> Self {
+        Self {
+            kind,
+            span: None,
+            source: None,
+            location: None,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {}", self.kind, span),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeErrorKind {
+    UndefinedBinding(String),
+    TypeError(String),
+    BreakOutsideLoop,
+    ConstRedefinition(String),
+    AssignToImmutable(String),
+    ContinueOutsideLoop,
+    ReturnOutsideFunction,
+    StackOverflow {
+        max_depth: usize,
+    },
+    NestingTooDeep {
+        max_depth: usize,
+    },
+    ArityMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    KeyNotFound(String),
+    IndexOutOfBounds {
+        index: i64,
+        len: usize,
+    },
+    SliceOutOfBounds {
+        start: i64,
+        end: i64,
+        len: usize,
+    },
+    DivisionByZero,
+    Overflow,
+    /// A function or closure was called after the environment it was defined
+    /// in had been dropped, which is only possible for one the host kept.
+    EnvDropped(String),
+}
+
+impl fmt::Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UndefinedBinding(name) => {
+                write!(f, "binding with name ‘{}’ does not exist", name)
+            }
+            Self::TypeError(msg) => write!(f, "{}", msg),
+            Self::ConstRedefinition(name) => {
+                write!(f, "cannot redefine constant ‘{}’", name)
+            }
+            Self::AssignToImmutable(name) => {
+                write!(f, "cannot assign twice to immutable binding ‘{}’", name)
+            }
+            Self::BreakOutsideLoop => write!(f, "`break` outside of a loop"),
+            Self::ContinueOutsideLoop => write!(f, "`continue` outside of a loop"),
+            Self::ReturnOutsideFunction => write!(f, "`return` outside of a function"),
+            Self::StackOverflow { max_depth } => write!(
+                f,
+                "stack overflow: maximum call depth {} exceeded",
+                max_depth
+            ),
+            Self::NestingTooDeep { max_depth } => write!(
+                f,
+                "stack overflow: code nested more than {} levels deep",
+                max_depth
+            ),
+            Self::ArityMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "function ‘{}’ takes {} argument(s) but {} were given",
+                name, expected, found
+            ),
+            Self::KeyNotFound(key) => write!(f, "key ‘{}’ not found in map", key),
+            Self::IndexOutOfBounds { index, len } => write!(
+                f,
+                "index {} is out of bounds for a list of length {}",
+                index, len
+            ),
+            Self::SliceOutOfBounds { start, end, len } => write!(
+                f,
+                "slice {}..{} is out of bounds for a list of length {}",
+                start, end, len
+            ),
+            Self::DivisionByZero => write!(f, "division by zero"),
+            Self::Overflow => write!(f, "integer overflow"),
+            Self::EnvDropped(name) => write!(
+                f,
+                "function ‘{}’ was defined in an environment that has been dropped",
+                name
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn span_on_first_line() {
+        assert_eq!(
+            Span::new("let a = 1", 4, 5),
+            Span {
+                start: 4,
+                end: 5,
+                line: 1,
+                column: 5,
+            },
+        );
+    }
+
+    #[test]
+    fn span_on_later_line() {
+        assert_eq!(
+            Span::new("{\n  let é = \n  }", 12, 13),
+            Span {
+                start: 12,
+                end: 13,
+                line: 2,
+                column: 10,
+            },
+        );
+    }
+
+    #[test]
+    fn keep_furthest_failure() {
+        assert_eq!(
+            ParseFailure::new("abc", "number").or(ParseFailure::new("c", "`=`")),
+            ParseFailure::new("c", "`=`"),
+        );
+    }
+
+    #[test]
+    fn merge_failures_at_same_position() {
+        assert_eq!(
+            ParseFailure::new("abc", "number")
+                .or(ParseFailure::new("abc", "identifier"))
+                .or(ParseFailure::new("abc", "number")),
+            ParseFailure {
+                remaining: 3,
+                expected: vec!["number".to_string(), "identifier".to_string()],
+                conclusive: false,
+            },
+        );
+    }
+
+    #[test]
+    fn keep_failure_of_code_nested_too_deeply() {
+        assert_eq!(
+            ParseFailure::new("abc", "number")
+                .or(ParseFailure::nested_too_deeply("abc"))
+                .or(ParseFailure::new("abc", "identifier")),
+            ParseFailure::nested_too_deeply("abc"),
+        );
+    }
+
+    #[test]
+    fn display_parse_error_with_alternatives() {
+        let err = ParseFailure::new(")", "number")
+            .or(ParseFailure::new(")", "identifier"))
+            .into_parse_error("1 + )");
+
+        assert_eq!(
+            err.to_string(),
+            "expected one of number, identifier at 1:5".to_string(),
+        );
+    }
+}
@@ -1,12 +1,104 @@
-use crate::{binding_usage::BindingUsage, block::Block, env::Env, utils, value::Value};
+use std::fmt;
+
+#[cfg(feature = "bigint")]
+use crate::bigint::{self, BigInt};
+use crate::binding_usage::BindingUsage;
+use crate::block::Block;
+use crate::closure::ClosureDef;
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::{ParseFailure, RuntimeError, RuntimeErrorKind};
+use crate::func_call::FuncCall;
+use crate::func_def::Return;
+use crate::if_expr::If;
+use crate::list::{Index, List, ListLiteral};
+use crate::loop_expr::{Break, For, Loop, While};
+use crate::map::{FieldAccess, Map, MapLiteral};
+use crate::string_literal::StringLiteral;
+use crate::utils;
+use crate::value::{Range, Value};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
-pub struct Number(pub i32);
+pub struct Number(pub i64);
 
 impl Number {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
-        let (s, number) = utils::extract_digits(s)?;
-        Ok((s, Self(number.parse().unwrap())))
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let after_sign = utils::extract_tag("-", s).unwrap_or(s);
+        let (remainder, _) = utils::extract_digits(after_sign)?;
+
+        let literal = &s[..s.len() - remainder.len()];
+
+        literal.parse().map(|n| (remainder, Self(n))).map_err(|_| {
+            ParseFailure::new(s, format!("number between {} and {}", i64::MIN, i64::MAX))
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Float(pub f64);
+
+impl Float {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let (remainder, literal) = utils::extract_float(s)?;
+
+        match literal.parse::<f64>() {
+            Ok(x) if x.is_finite() => Ok((remainder, Self(x))),
+            _ => Err(ParseFailure::new(s, "float within range")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum UnaryOperator {
+    Neg,
+    Plus,
+    Not,
+}
+
+impl UnaryOperator {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        utils::extract_tag("-", s)
+            .map(|s| (s, Self::Neg))
+            .or_else(|_| utils::extract_tag("+", s).map(|s| (s, Self::Plus)))
+            .or_else(|_| utils::extract_tag("!", s).map(|s| (s, Self::Not)))
+            .map_err(|_| ParseFailure::new(s, "unary operator"))
+    }
+
+    fn apply(self, operand: Value) -> Result<Value, RuntimeError> {
+        match (self, operand) {
+            (Self::Neg, Value::Number(n)) => match n.checked_neg() {
+                Some(n) => Ok(Value::Number(n)),
+                #[cfg(feature = "bigint")]
+                None => Ok(bigint::normalize(-BigInt::from(n))),
+                #[cfg(not(feature = "bigint"))]
+                None => Err(RuntimeErrorKind::Overflow.into()),
+            },
+            (Self::Plus, Value::Number(n)) => Ok(Value::Number(n)),
+            #[cfg(feature = "bigint")]
+            (Self::Neg, Value::BigInt(n)) => Ok(bigint::normalize(-n)),
+            #[cfg(feature = "bigint")]
+            (Self::Plus, Value::BigInt(n)) => Ok(Value::BigInt(n)),
+            (Self::Neg, Value::Float(x)) => Ok(Value::Float(-x)),
+            (Self::Plus, Value::Float(x)) => Ok(Value::Float(x)),
+            (Self::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+            (_, operand) => Err(RuntimeErrorKind::TypeError(format!(
+                "cannot apply `{}` to {}",
+                self, operand
+            ))
+            .into()),
+        }
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Neg => "-",
+            Self::Plus => "+",
+            Self::Not => "!",
+        };
+
+        write!(f, "{}", symbol)
     }
 }
 
@@ -16,104 +108,692 @@ pub enum Operator {
     Sub,
     Mul,
     Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
 }
 
 impl Operator {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
         utils::extract_tag("+", s)
             .map(|s| (s, Self::Add))
             .or_else(|_| utils::extract_tag("-", s).map(|s| (s, Self::Sub)))
             .or_else(|_| utils::extract_tag("*", s).map(|s| (s, Self::Mul)))
-            .or_else(|_| utils::extract_tag("/", s).map(|s| (s, Self::Div)))
+            // A slash that starts a comment is not a division.
+            .or_else(|_| match utils::extract_tag("/", s) {
+                Ok(after_slash) if !after_slash.starts_with(['/', '*']) => {
+                    Ok((after_slash, Self::Div))
+                }
+                _ => Err(ParseFailure::new(s, "`/`")),
+            })
+            .or_else(|_| utils::extract_tag("%", s).map(|s| (s, Self::Rem)))
+            .or_else(|_| utils::extract_tag("==", s).map(|s| (s, Self::Eq)))
+            .or_else(|_| utils::extract_tag("!=", s).map(|s| (s, Self::Ne)))
+            .or_else(|_| utils::extract_tag("<=", s).map(|s| (s, Self::Le)))
+            .or_else(|_| utils::extract_tag("<", s).map(|s| (s, Self::Lt)))
+            .or_else(|_| utils::extract_tag(">=", s).map(|s| (s, Self::Ge)))
+            .or_else(|_| utils::extract_tag(">", s).map(|s| (s, Self::Gt)))
+            .or_else(|_| utils::extract_tag("&&", s).map(|s| (s, Self::And)))
+            .or_else(|_| utils::extract_tag("||", s).map(|s| (s, Self::Or)))
+            .map_err(|_| ParseFailure::new(s, "operator"))
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct Operation {
-    pub lhs: Number,
-    pub rhs: Number,
-    pub op: Operator,
-}
+    /// Binding power of the operator: higher binds tighter.
+    fn precedence(self) -> u8 {
+        match self {
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => 3,
+            Self::Add | Self::Sub => 4,
+            Self::Mul | Self::Div | Self::Rem => 5,
+        }
+    }
 
-impl Operation {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
-        let (s, lhs) = Number::new(s)?;
-        let (s, _) = utils::extract_whitespaces(s);
+    pub(crate) fn apply(self, lhs: Value, rhs: Value) -> Result<Value, RuntimeError> {
+        match (lhs, rhs) {
+            (Value::Number(lhs), Value::Number(rhs)) => self.apply_to_numbers(lhs, rhs),
+            // As soon as one operand is a float, the other one is promoted.
+            (Value::Float(lhs), Value::Float(rhs)) => self.apply_to_floats(lhs, rhs),
+            (Value::Number(lhs), Value::Float(rhs)) => self.apply_to_floats(lhs as f64, rhs),
+            (Value::Float(lhs), Value::Number(rhs)) => self.apply_to_floats(lhs, rhs as f64),
+            #[cfg(feature = "bigint")]
+            (Value::BigInt(lhs), Value::BigInt(rhs)) => self.apply_to_big_ints(lhs, rhs),
+            #[cfg(feature = "bigint")]
+            (Value::BigInt(lhs), Value::Number(rhs)) => self.apply_to_big_ints(lhs, rhs.into()),
+            #[cfg(feature = "bigint")]
+            (Value::Number(lhs), Value::BigInt(rhs)) => self.apply_to_big_ints(lhs.into(), rhs),
+            #[cfg(feature = "bigint")]
+            (Value::BigInt(lhs), Value::Float(rhs)) => {
+                self.apply_to_floats(bigint::to_f64(&lhs), rhs)
+            }
+            #[cfg(feature = "bigint")]
+            (Value::Float(lhs), Value::BigInt(rhs)) => {
+                self.apply_to_floats(lhs, bigint::to_f64(&rhs))
+            }
+            (Value::Bool(lhs), Value::Bool(rhs)) => self.apply_to_bools(lhs, rhs),
+            (Value::String(lhs), Value::String(rhs)) => self.apply_to_strings(lhs, rhs),
+            (Value::List(lhs), Value::List(rhs)) => self.apply_to_lists(lhs, rhs),
+            (Value::Map(lhs), Value::Map(rhs)) => self.apply_to_maps(lhs, rhs),
+            (lhs, rhs) => Err(self.invalid_operands(&lhs, &rhs)),
+        }
+    }
 
-        let (s, op) = Operator::new(s)?;
-        let (s, _) = utils::extract_whitespaces(s);
+    fn apply_to_numbers(self, lhs: i64, rhs: i64) -> Result<Value, RuntimeError> {
+        let result = match self {
+            Self::Eq => return Ok(Value::Bool(lhs == rhs)),
+            Self::Ne => return Ok(Value::Bool(lhs != rhs)),
+            Self::Lt => return Ok(Value::Bool(lhs < rhs)),
+            Self::Le => return Ok(Value::Bool(lhs <= rhs)),
+            Self::Gt => return Ok(Value::Bool(lhs > rhs)),
+            Self::Ge => return Ok(Value::Bool(lhs >= rhs)),
+            Self::And | Self::Or => {
+                return Err(self.invalid_operands(&Value::Number(lhs), &Value::Number(rhs)))
+            }
+            Self::Div | Self::Rem if rhs == 0 => {
+                return Err(RuntimeErrorKind::DivisionByZero.into())
+            }
+            Self::Add => lhs.checked_add(rhs),
+            Self::Sub => lhs.checked_sub(rhs),
+            Self::Mul => lhs.checked_mul(rhs),
+            Self::Div => lhs.checked_div(rhs),
+            Self::Rem => lhs.checked_rem(rhs),
+        };
+
+        match result {
+            Some(n) => Ok(Value::Number(n)),
+            #[cfg(feature = "bigint")]
+            None => self.apply_to_big_ints(lhs.into(), rhs.into()),
+            #[cfg(not(feature = "bigint"))]
+            None => Err(RuntimeErrorKind::Overflow.into()),
+        }
+    }
+
+    /// Only reached once a result has outgrown `i64`; results that fit again
+    /// are turned back into plain numbers.
+    #[cfg(feature = "bigint")]
+    fn apply_to_big_ints(self, lhs: BigInt, rhs: BigInt) -> Result<Value, RuntimeError> {
+        let result = match self {
+            Self::Eq => return Ok(Value::Bool(lhs == rhs)),
+            Self::Ne => return Ok(Value::Bool(lhs != rhs)),
+            Self::Lt => return Ok(Value::Bool(lhs < rhs)),
+            Self::Le => return Ok(Value::Bool(lhs <= rhs)),
+            Self::Gt => return Ok(Value::Bool(lhs > rhs)),
+            Self::Ge => return Ok(Value::Bool(lhs >= rhs)),
+            Self::And | Self::Or => {
+                return Err(self.invalid_operands(&bigint::normalize(lhs), &bigint::normalize(rhs)))
+            }
+            Self::Div | Self::Rem if rhs == BigInt::from(0) => {
+                return Err(RuntimeErrorKind::DivisionByZero.into())
+            }
+            Self::Add => lhs + rhs,
+            Self::Sub => lhs - rhs,
+            Self::Mul => lhs * rhs,
+            Self::Div => lhs / rhs,
+            Self::Rem => lhs % rhs,
+        };
+
+        Ok(bigint::normalize(result))
+    }
+
+    /// Follows IEEE 754, except that dividing by zero is an error just like
+    /// it is for integers.
+    fn apply_to_floats(self, lhs: f64, rhs: f64) -> Result<Value, RuntimeError> {
+        let result = match self {
+            Self::Eq => return Ok(Value::Bool(lhs == rhs)),
+            Self::Ne => return Ok(Value::Bool(lhs != rhs)),
+            Self::Lt => return Ok(Value::Bool(lhs < rhs)),
+            Self::Le => return Ok(Value::Bool(lhs <= rhs)),
+            Self::Gt => return Ok(Value::Bool(lhs > rhs)),
+            Self::Ge => return Ok(Value::Bool(lhs >= rhs)),
+            Self::And | Self::Or => {
+                return Err(self.invalid_operands(&Value::Float(lhs), &Value::Float(rhs)))
+            }
+            Self::Div | Self::Rem if rhs == 0.0 => {
+                return Err(RuntimeErrorKind::DivisionByZero.into())
+            }
+            Self::Add => lhs + rhs,
+            Self::Sub => lhs - rhs,
+            Self::Mul => lhs * rhs,
+            Self::Div => lhs / rhs,
+            Self::Rem => lhs % rhs,
+        };
+
+        Ok(Value::Float(result))
+    }
+
+    fn apply_to_bools(self, lhs: bool, rhs: bool) -> Result<Value, RuntimeError> {
+        match self {
+            Self::Eq => Ok(Value::Bool(lhs == rhs)),
+            Self::Ne => Ok(Value::Bool(lhs != rhs)),
+            Self::And => Ok(Value::Bool(lhs && rhs)),
+            Self::Or => Ok(Value::Bool(lhs || rhs)),
+            _ => Err(self.invalid_operands(&Value::Bool(lhs), &Value::Bool(rhs))),
+        }
+    }
+
+    fn apply_to_strings(self, lhs: String, rhs: String) -> Result<Value, RuntimeError> {
+        match self {
+            Self::Add => Ok(Value::String(lhs + &rhs)),
+            Self::Eq => Ok(Value::Bool(lhs == rhs)),
+            Self::Ne => Ok(Value::Bool(lhs != rhs)),
+            _ => Err(self.invalid_operands(&Value::String(lhs), &Value::String(rhs))),
+        }
+    }
 
-        let (s, rhs) = Number::new(s)?;
+    /// Concatenation creates a new list and leaves both operands untouched.
+    fn apply_to_lists(self, lhs: List, rhs: List) -> Result<Value, RuntimeError> {
+        match self {
+            Self::Add => {
+                let mut items = lhs.to_vec();
+                items.extend(rhs.iter().cloned());
+                Ok(Value::list(items))
+            }
+            Self::Eq => Ok(Value::Bool(lhs == rhs)),
+            Self::Ne => Ok(Value::Bool(lhs != rhs)),
+            _ => Err(self.invalid_operands(&Value::List(lhs), &Value::List(rhs))),
+        }
+    }
 
-        Ok((s, Self { lhs, rhs, op }))
+    fn apply_to_maps(self, lhs: Map, rhs: Map) -> Result<Value, RuntimeError> {
+        match self {
+            Self::Eq => Ok(Value::Bool(lhs == rhs)),
+            Self::Ne => Ok(Value::Bool(lhs != rhs)),
+            _ => Err(self.invalid_operands(&Value::Map(lhs), &Value::Map(rhs))),
+        }
     }
 
-    pub(crate) fn eval(&self) -> Result<Value, String> {
-        let Number(lhs) = self.lhs;
-        let Number(rhs) = self.rhs;
+    fn invalid_operands(self, lhs: &Value, rhs: &Value) -> RuntimeError {
+        RuntimeErrorKind::TypeError(format!("cannot apply `{}` to {} and {}", self, lhs, rhs))
+            .into()
+    }
+}
 
-        let result = match self.op {
-            Operator::Add => lhs + rhs,
-            Operator::Sub => lhs - rhs,
-            Operator::Mul => lhs * rhs,
-            Operator::Div => lhs / rhs,
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Rem => "%",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
         };
 
-        Ok(Value::Number(result))
+        write!(f, "{}", symbol)
     }
 }
 
 #[derive(Debug, PartialEq)]
 pub enum Expression {
     Number(Number),
+    /// An integer literal outside the range of `i64`.
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    Float(Float),
+    Bool(bool),
+    String(StringLiteral),
+    Unary {
+        op: UnaryOperator,
+        operand: Box<Expression>,
+    },
     Operation {
-        lhs: Number,
-        rhs: Number,
+        lhs: Box<Expression>,
+        rhs: Box<Expression>,
         op: Operator,
     },
     Block(Block),
     BindingUsage(BindingUsage),
+    If(If),
+    While(While),
+    Loop(Loop),
+    Break(Break),
+    Continue,
+    FuncCall(FuncCall),
+    Return(Return),
+    Closure(ClosureDef),
+    List(ListLiteral),
+    Index(Index),
+    Map(MapLiteral),
+    FieldAccess(FieldAccess),
+    Range {
+        start: Box<Expression>,
+        end: Box<Expression>,
+        inclusive: bool,
+    },
+    For(For),
 }
 
 impl Expression {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
-        Operation::new(s)
-            .map(|(s, operation)| {
-                (
-                    s,
-                    Self::Operation {
-                        lhs: operation.lhs,
-                        rhs: operation.rhs,
-                        op: operation.op,
-                    },
-                )
-            })
-            .or_else(|_| Number::new(s).map(|(s, number)| (s, Self::Number(number))))
-            .or_else(|_| {
-                BindingUsage::new(s)
-                    .map(|(s, binding_usage)| (s, Self::BindingUsage(binding_usage)))
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let (s, start) = Self::new_without_range(s)?;
+
+        // A range binds more loosely than any binary operator, so that
+        // `0..n + 1` ends at `n + 1`.
+        let (after_whitespace, whitespace) = utils::extract_whitespaces(s);
+        if whitespace.contains('\n') {
+            return Ok((s, start));
+        }
+        let (after_dots, inclusive) = match utils::extract_tag("..=", after_whitespace) {
+            Ok(after_dots) => (after_dots, true),
+            Err(_) => match utils::extract_tag("..", after_whitespace) {
+                Ok(after_dots) => (after_dots, false),
+                Err(_) => return Ok((s, start)),
+            },
+        };
+        let (after_dots, _) = utils::extract_whitespaces(after_dots);
+
+        let (s, end) = Self::new_without_range(after_dots)?;
+
+        Ok((
+            s,
+            Self::Range {
+                start: Box::new(start),
+                end: Box::new(end),
+                inclusive,
+            },
+        ))
+    }
+
+    pub(crate) fn new_without_range(s: &str) -> Result<(&str, Self), ParseFailure> {
+        Self::new_with_precedence(s, 0)
+    }
+
+    /// Precedence climbing: parses an operand, then keeps folding in binary
+    /// operators that bind at least as tightly as `min_precedence`. The right
+    /// operand is parsed one level higher so operators associate to the left.
+    fn new_with_precedence(s: &str, min_precedence: u8) -> Result<(&str, Self), ParseFailure> {
+        let (mut s, mut lhs) = Self::new_operand(s)?;
+
+        loop {
+            // An expression may continue on the next line after an operator,
+            // but not before one: `-a` on a line of its own is a statement.
+            let (after_whitespace, whitespace) = utils::extract_whitespaces(s);
+            if whitespace.contains('\n') {
+                break;
+            }
+
+            let (after_op, op) = match Operator::new(after_whitespace) {
+                Ok((after_op, op)) if op.precedence() >= min_precedence => (after_op, op),
+                _ => break,
+            };
+            let (after_op, _) = utils::extract_whitespaces(after_op);
+
+            let (new_s, rhs) = Self::new_with_precedence(after_op, op.precedence() + 1)?;
+            s = new_s;
+
+            lhs = Self::Operation {
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                op,
+            };
+        }
+
+        Ok((s, lhs))
+    }
+
+    /// A primary expression followed by any number of call, index and field
+    /// access suffixes. A suffix has to follow the operand directly so that a
+    /// parenthesised expression or a list on the next line is not taken as
+    /// part of it.
+    fn new_operand(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let (mut s, mut operand) = Self::new_primary(s)?;
+
+        loop {
+            if s.starts_with('(') {
+                let (new_s, args) = utils::extract_list("(", ")", Self::new, s)?;
+                s = new_s;
+
+                operand = Self::FuncCall(FuncCall {
+                    callee: Box::new(operand),
+                    args,
+                });
+            } else if s.starts_with('[') {
+                let (new_s, index) = Index::new(operand, s)?;
+                s = new_s;
+
+                operand = Self::Index(index);
+            } else if s.starts_with('.') && !s.starts_with("..") {
+                let (new_s, field_access) = FieldAccess::new(operand, s)?;
+                s = new_s;
+
+                operand = Self::FieldAccess(field_access);
+            } else {
+                return Ok((s, operand));
+            }
+        }
+    }
+
+    fn new_primary(s: &str) -> Result<(&str, Self), ParseFailure> {
+        // Nothing but a number literal can start with a digit, so an
+        // out-of-range literal is reported as such rather than being merged
+        // with the failures of the other alternatives.
+        let literal_start = utils::extract_tag("-", s).unwrap_or(s);
+        if literal_start.starts_with(|c: char| c.is_ascii_digit()) {
+            return if utils::extract_float(s).is_ok() {
+                Float::new(s).map(|(s, float)| (s, Self::Float(float)))
+            } else {
+                Self::new_integer(s)
+            };
+        }
+
+        // Likewise, an unterminated string or a bad escape is reported where
+        // it goes wrong.
+        if s.starts_with('"') {
+            return StringLiteral::new(s).map(|(s, literal)| (s, Self::String(literal)));
+        }
+
+        // Every other alternative may contain further expressions.
+        utils::extract_nested(
+            |s| {
+                Self::new_bool(s)
+                    .map_err(|err| {
+                        ParseFailure::new(s, "number")
+                            .or(ParseFailure::new(s, "string"))
+                            .or(err)
+                    })
+                    .or_else(|err| Self::new_unary(s).map_err(|other| err.or(other)))
+                    .or_else(|err| {
+                        If::new(s)
+                            .map(|(s, if_expr)| (s, Self::If(if_expr)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        While::new(s)
+                            .map(|(s, while_expr)| (s, Self::While(while_expr)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        Loop::new(s)
+                            .map(|(s, loop_expr)| (s, Self::Loop(loop_expr)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        For::new(s)
+                            .map(|(s, for_expr)| (s, Self::For(for_expr)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        Break::new(s)
+                            .map(|(s, break_expr)| (s, Self::Break(break_expr)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        Return::new(s)
+                            .map(|(s, return_expr)| (s, Self::Return(return_expr)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        utils::extract_keyword("continue", s)
+                            .map(|s| (s, Self::Continue))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        BindingUsage::new(s)
+                            .map(|(s, binding_usage)| (s, Self::BindingUsage(binding_usage)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        ClosureDef::new(s)
+                            .map(|(s, closure)| (s, Self::Closure(closure)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        ListLiteral::new(s)
+                            .map(|(s, list)| (s, Self::List(list)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        MapLiteral::new(s)
+                            .map(|(s, map)| (s, Self::Map(map)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| {
+                        Block::new(s)
+                            .map(|(s, block)| (s, Self::Block(block)))
+                            .map_err(|other| err.or(other))
+                    })
+                    .or_else(|err| Self::new_parenthesized(s).map_err(|other| err.or(other)))
+            },
+            s,
+        )
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    fn new_integer(s: &str) -> Result<(&str, Self), ParseFailure> {
+        Number::new(s).map(|(s, number)| (s, Self::Number(number)))
+    }
+
+    /// With the `bigint` feature, any integer that can be computed can also be
+    /// written as a literal.
+    #[cfg(feature = "bigint")]
+    fn new_integer(s: &str) -> Result<(&str, Self), ParseFailure> {
+        Number::new(s)
+            .map(|(s, number)| (s, Self::Number(number)))
+            .or_else(|err| {
+                let after_sign = utils::extract_tag("-", s).unwrap_or(s);
+                let (remainder, _) = utils::extract_digits(after_sign)?;
+                let literal = &s[..s.len() - remainder.len()];
+
+                literal
+                    .parse()
+                    .map(|n| (remainder, Self::BigInt(n)))
+                    .map_err(|_| err)
             })
-            .or_else(|_| Block::new(s).map(|(s, block)| (s, Self::Block(block))))
     }
 
-    pub(crate) fn eval(&self, env: &Env) -> Result<Value, String> {
-        match self {
+    fn new_bool(s: &str) -> Result<(&str, Self), ParseFailure> {
+        utils::extract_keyword("true", s)
+            .map(|s| (s, Self::Bool(true)))
+            .or_else(|_| utils::extract_keyword("false", s).map(|s| (s, Self::Bool(false))))
+            .map_err(|_| ParseFailure::new(s, "boolean"))
+    }
+
+    /// Unary operators bind tighter than any binary operator, so their
+    /// operand is a single operand rather than a whole expression.
+    fn new_unary(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let (s, op) = UnaryOperator::new(s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, operand) = Self::new_operand(s)?;
+
+        Ok((
+            s,
+            Self::Unary {
+                op,
+                operand: Box::new(operand),
+            },
+        ))
+    }
+
+    fn new_parenthesized(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_tag("(", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, expression) = Self::new(s)?;
+
+        let (s, _) = utils::extract_whitespaces(s);
+        let s = utils::extract_tag(")", s)?;
+
+        Ok((s, expression))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let _eval = env.enter_eval()?;
+
+        utils::with_stack(|| match self {
             Self::Number(Number(n)) => Ok(Value::Number(*n)),
-            Self::Operation { lhs, rhs, op } => Operation {
-                lhs: *lhs,
-                rhs: *rhs,
-                op: *op,
-            }
-            .eval(),
-            Self::BindingUsage(binding_usage) => binding_usage.eval(env),
+            #[cfg(feature = "bigint")]
+            Self::BigInt(n) => Ok(Value::BigInt(n.clone())),
+            Self::Float(Float(x)) => Ok(Value::Float(*x)),
+            Self::Bool(b) => Ok(Value::Bool(*b)),
+            Self::String(literal) => literal.eval(env),
+            Self::Unary { op, operand } => Self::eval_unary(*op, operand, env),
+            Self::Operation { lhs, rhs, op } => Self::eval_operation(lhs, rhs, *op, env),
+            Self::BindingUsage(binding_usage) => binding_usage.eval(env).map_err(ControlFlow::from),
             Self::Block(block) => block.eval(env),
+            Self::If(if_expr) => if_expr.eval(env),
+            Self::While(while_expr) => while_expr.eval(env),
+            Self::Loop(loop_expr) => loop_expr.eval(env),
+            Self::Break(break_expr) => break_expr.eval(env),
+            Self::Continue => Err(ControlFlow::Continue),
+            Self::FuncCall(func_call) => func_call.eval(env),
+            Self::Return(return_expr) => return_expr.eval(env),
+            Self::Closure(closure) => Ok(closure.eval(env)),
+            Self::List(list) => list.eval(env),
+            Self::Index(index) => index.eval(env),
+            Self::Map(map) => map.eval(env),
+            Self::FieldAccess(field_access) => field_access.eval(env),
+            Self::Range {
+                start,
+                end,
+                inclusive,
+            } => Self::eval_range(start, end, *inclusive, env),
+            Self::For(for_expr) => for_expr.eval(env),
+        })
+    }
+
+    // The arms of `eval` that need more than a single call live in their own
+    // functions, which keeps the stack frame of `eval` itself small. Every
+    // level of recursion in a script goes through it several times.
+
+    fn eval_unary(
+        op: UnaryOperator,
+        operand: &Expression,
+        env: &Env,
+    ) -> Result<Value, ControlFlow> {
+        Ok(op.apply(operand.eval(env)?)?)
+    }
+
+    /// Operators associate to the left, so a chain like `1 + 2 + 3` nests in
+    /// its left operand, as deep as the chain is long. The chain is walked
+    /// down and folded in a loop instead, so that it takes a single level of
+    /// evaluation however long it gets.
+    fn eval_operation(
+        lhs: &Expression,
+        rhs: &Expression,
+        op: Operator,
+        env: &Env,
+    ) -> Result<Value, ControlFlow> {
+        if !matches!(lhs, Self::Operation { .. }) {
+            let lhs = lhs.eval(env)?;
+            return Self::fold_operation(lhs, rhs, op, env);
+        }
+
+        let mut chain = vec![(op, rhs)];
+        let mut first = lhs;
+        while let Self::Operation { lhs, rhs, op } = first {
+            chain.push((*op, rhs));
+            first = lhs;
+        }
+
+        let mut value = first.eval(env)?;
+        for (op, rhs) in chain.into_iter().rev() {
+            value = Self::fold_operation(value, rhs, op, env)?;
+        }
+
+        Ok(value)
+    }
+
+    fn fold_operation(
+        lhs: Value,
+        rhs: &Expression,
+        op: Operator,
+        env: &Env,
+    ) -> Result<Value, ControlFlow> {
+        // `&&` and `||` short-circuit: the right operand is only evaluated
+        // when it can still change the result.
+        match (op, &lhs) {
+            (Operator::And, Value::Bool(false)) | (Operator::Or, Value::Bool(true)) => Ok(lhs),
+            _ => op.apply(lhs, rhs.eval(env)?).map_err(ControlFlow::from),
+        }
+    }
+
+    fn eval_range(
+        start: &Expression,
+        end: &Expression,
+        inclusive: bool,
+        env: &Env,
+    ) -> Result<Value, ControlFlow> {
+        Ok(Value::Range(Range {
+            start: eval_range_bound(start, env)?,
+            end: eval_range_bound(end, env)?,
+            inclusive,
+        }))
+    }
+}
+
+fn eval_range_bound(bound: &Expression, env: &Env) -> Result<i64, ControlFlow> {
+    match bound.eval(env)? {
+        Value::Number(n) => Ok(n),
+        bound => Err(RuntimeError::from(RuntimeErrorKind::TypeError(format!(
+            "range bounds must be numbers, found {}",
+            bound
+        )))
+        .into()),
+    }
+}
+
+// The default drop recurses into the operands of an expression, which would
+// overflow the stack on a long operator chain. The operands are moved onto a
+// stack on the heap instead, so each expression is dropped without any left.
+impl Drop for Expression {
+    fn drop(&mut self) {
+        let mut operands = Vec::new();
+        self.take_operands(&mut operands);
+
+        while let Some(mut operand) = operands.pop() {
+            operand.take_operands(&mut operands);
+        }
+    }
+}
+
+impl Expression {
+    fn take_operands(&mut self, operands: &mut Vec<Expression>) {
+        let mut take = |operand: &mut Box<Expression>| {
+            if operand.has_operands() {
+                operands.push(std::mem::replace(&mut **operand, Self::Continue));
+            }
+        };
+
+        match self {
+            Self::Unary { operand, .. } => take(operand),
+            Self::Operation { lhs, rhs, .. } => {
+                take(lhs);
+                take(rhs);
+            }
+            Self::Range { start, end, .. } => {
+                take(start);
+                take(end);
+            }
+            _ => {}
         }
     }
+
+    fn has_operands(&self) -> bool {
+        matches!(
+            self,
+            Self::Unary { .. } | Self::Operation { .. } | Self::Range { .. }
+        )
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::binding_def::BindingKind;
     use crate::binding_usage::BindingUsage;
     use crate::env::Env;
     use crate::value::Value;
@@ -123,6 +803,31 @@ mod tests {
         assert_eq!(Number::new("123"), Ok(("", Number(123))));
     }
 
+    #[test]
+    fn parse_negative_number() {
+        assert_eq!(Number::new("-123"), Ok(("", Number(-123))));
+    }
+
+    #[test]
+    fn parse_smallest_number() {
+        assert_eq!(
+            Number::new("-9223372036854775808"),
+            Ok(("", Number(i64::MIN)))
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn cannot_parse_number_that_overflows() {
+        assert_eq!(
+            Expression::new("9223372036854775808"),
+            Err(ParseFailure::new(
+                "9223372036854775808",
+                "number between -9223372036854775808 and 9223372036854775807",
+            )),
+        );
+    }
+
     #[test]
     fn parse_add_op() {
         assert_eq!(Operator::new("+"), Ok(("", Operator::Add)));
@@ -143,6 +848,11 @@ mod tests {
         assert_eq!(Operator::new("/"), Ok(("", Operator::Div)));
     }
 
+    #[test]
+    fn parse_rem_op() {
+        assert_eq!(Operator::new("%"), Ok(("", Operator::Rem)));
+    }
+
     #[test]
     fn parse_one_plus_two() {
         assert_eq!(
@@ -150,8 +860,8 @@ mod tests {
             Ok((
                 "",
                 Expression::Operation {
-                    lhs: Number(1),
-                    rhs: Number(2),
+                    lhs: Box::new(Expression::Number(Number(1))),
+                    rhs: Box::new(Expression::Number(Number(2))),
                     op: Operator::Add
                 }
             ))
@@ -165,8 +875,8 @@ mod tests {
             Ok((
                 "",
                 Expression::Operation {
-                    lhs: Number(1),
-                    rhs: Number(2),
+                    lhs: Box::new(Expression::Number(Number(1))),
+                    rhs: Box::new(Expression::Number(Number(2))),
                     op: Operator::Add
                 }
             ))
@@ -177,8 +887,8 @@ mod tests {
     fn eval_add() {
         assert_eq!(
             Expression::Operation {
-                lhs: Number(10),
-                rhs: Number(10),
+                lhs: Box::new(Expression::Number(Number(10))),
+                rhs: Box::new(Expression::Number(Number(10))),
                 op: Operator::Add,
             }
             .eval(&Env::default()),
@@ -190,8 +900,8 @@ mod tests {
     fn eval_sub() {
         assert_eq!(
             Expression::Operation {
-                lhs: Number(1),
-                rhs: Number(5),
+                lhs: Box::new(Expression::Number(Number(1))),
+                rhs: Box::new(Expression::Number(Number(5))),
                 op: Operator::Sub,
             }
             .eval(&Env::default()),
@@ -203,8 +913,8 @@ mod tests {
     fn eval_mul() {
         assert_eq!(
             Expression::Operation {
-                lhs: Number(5),
-                rhs: Number(6),
+                lhs: Box::new(Expression::Number(Number(5))),
+                rhs: Box::new(Expression::Number(Number(6))),
                 op: Operator::Mul,
             }
             .eval(&Env::default()),
@@ -216,8 +926,8 @@ mod tests {
     fn eval_div() {
         assert_eq!(
             Expression::Operation {
-                lhs: Number(200),
-                rhs: Number(20),
+                lhs: Box::new(Expression::Number(Number(200))),
+                rhs: Box::new(Expression::Number(Number(20))),
                 op: Operator::Div,
             }
             .eval(&Env::default()),
@@ -235,8 +945,9 @@ mod tests {
 
     #[test]
     fn eval_binding_usage() {
-        let mut env = Env::default();
-        env.store_binding("ten".to_string(), Value::Number(10));
+        let env = Env::default();
+        env.store_binding("ten".to_string(), Value::Number(10), BindingKind::Let)
+            .unwrap();
 
         assert_eq!(
             Expression::BindingUsage(BindingUsage {
@@ -246,4 +957,697 @@ mod tests {
             Ok(Value::Number(10)),
         );
     }
+
+    #[test]
+    fn parse_mul_before_add() {
+        assert_eq!(
+            Expression::new("1 + 2 * 3"),
+            Ok((
+                "",
+                Expression::Operation {
+                    lhs: Box::new(Expression::Number(Number(1))),
+                    rhs: Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::Number(Number(2))),
+                        rhs: Box::new(Expression::Number(Number(3))),
+                        op: Operator::Mul,
+                    }),
+                    op: Operator::Add,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_sub_is_left_associative() {
+        assert_eq!(
+            Expression::new("10 - 4 - 3"),
+            Ok((
+                "",
+                Expression::Operation {
+                    lhs: Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::Number(Number(10))),
+                        rhs: Box::new(Expression::Number(Number(4))),
+                        op: Operator::Sub,
+                    }),
+                    rhs: Box::new(Expression::Number(Number(3))),
+                    op: Operator::Sub,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_parenthesized_operation() {
+        assert_eq!(
+            Expression::new("(1 + 2) * x"),
+            Ok((
+                "",
+                Expression::Operation {
+                    lhs: Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::Number(Number(1))),
+                        rhs: Box::new(Expression::Number(Number(2))),
+                        op: Operator::Add,
+                    }),
+                    rhs: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "x".to_string(),
+                    })),
+                    op: Operator::Mul,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_operation_stops_before_next_statement() {
+        assert_eq!(
+            Expression::new("a + 1\nlet b = 2"),
+            Ok((
+                "\nlet b = 2",
+                Expression::Operation {
+                    lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "a".to_string(),
+                    })),
+                    rhs: Box::new(Expression::Number(Number(1))),
+                    op: Operator::Add,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_operator_on_next_line_as_new_statement() {
+        assert_eq!(
+            Expression::new("5\n-a"),
+            Ok(("\n-a", Expression::Number(Number(5)))),
+        );
+        assert_eq!(
+            Expression::new("0 // to\n..5"),
+            Ok((" // to\n..5", Expression::Number(Number(0)))),
+        );
+    }
+
+    #[test]
+    fn parse_operation_continued_after_operator() {
+        let (_, expression) = Expression::new("1 +\n  2 * // two\n  3").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Number(7)));
+    }
+
+    #[test]
+    fn eval_unary_minus_on_line_after_binding() {
+        let (_, block) = Block::new("{\n  let a = 5\n  -a\n}").unwrap();
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(-5)));
+    }
+
+    #[test]
+    fn cannot_parse_unclosed_parenthesis() {
+        assert_eq!(Expression::new("(1 + 2"), Err(ParseFailure::new("", "`)`")),);
+    }
+
+    #[test]
+    fn parse_nesting_up_to_max_depth() {
+        let depth = utils::MAX_NESTING_DEPTH;
+        let source = format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert_eq!(
+            Expression::new(&source),
+            Ok(("", Expression::Number(Number(1)))),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_nesting_beyond_max_depth() {
+        let depth = utils::MAX_NESTING_DEPTH;
+        let sources = [
+            format!("{}1{}", "(".repeat(1000), ")".repeat(1000)),
+            format!("{}1{}", "{".repeat(1000), "}".repeat(1000)),
+            format!("{}1{}", "[".repeat(1000), "]".repeat(1000)),
+            format!("{}1", "-".repeat(20_000)),
+        ];
+
+        for source in &sources {
+            let at_limit = source.match_indices(&source[..1]).nth(depth).unwrap().0;
+
+            assert_eq!(
+                Expression::new(source),
+                Err(ParseFailure::nested_too_deeply(&source[at_limit..])),
+            );
+        }
+
+        // The depth is left again after failing.
+        assert_eq!(
+            Expression::new("((1))"),
+            Ok(("", Expression::Number(Number(1)))),
+        );
+    }
+
+    #[test]
+    fn eval_operation_with_precedence() {
+        let (_, expression) = Expression::new("2 + 3 * 4 - 6 / 2").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Number(11)));
+    }
+
+    #[test]
+    fn eval_long_flat_chain() {
+        let source = vec!["1"; 100_000].join(" + ");
+
+        // Both evaluating and dropping the chain would overflow a small
+        // stack if every operator took a level of recursion.
+        let result = std::thread::Builder::new()
+            .stack_size(256 * 1024)
+            .spawn(move || {
+                let (_, expression) = Expression::new(&source).unwrap();
+                expression.eval(&Env::default()).unwrap().to_string()
+            })
+            .unwrap()
+            .join()
+            .unwrap();
+
+        assert_eq!(result, "100000");
+    }
+
+    #[test]
+    fn eval_chain_of_logical_operators() {
+        let env = Env::default();
+        let (_, expression) = Expression::new("false && x || true && 1 < 2").unwrap();
+
+        assert_eq!(expression.eval(&env), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_operation_with_bindings_and_blocks() {
+        let env = Env::default();
+        env.store_binding("x".to_string(), Value::Number(4), BindingKind::Let)
+            .unwrap();
+
+        let (_, expression) = Expression::new("(x + 1) * { 2 }").unwrap();
+        assert_eq!(expression.eval(&env), Ok(Value::Number(10)));
+    }
+
+    #[test]
+    fn eval_operation_on_unit() {
+        let (_, expression) = Expression::new("1 + {}").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::TypeError("cannot apply `+` to 1 and Unit".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_missing_operand() {
+        assert_eq!(
+            Expression::new("1 + )"),
+            Err(ParseFailure::new(")", "number")
+                .or(ParseFailure::new(")", "string"))
+                .or(ParseFailure::new(")", "boolean"))
+                .or(ParseFailure::new(")", "unary operator"))
+                .or(ParseFailure::new(")", "`if`"))
+                .or(ParseFailure::new(")", "`while`"))
+                .or(ParseFailure::new(")", "`loop`"))
+                .or(ParseFailure::new(")", "`for`"))
+                .or(ParseFailure::new(")", "`break`"))
+                .or(ParseFailure::new(")", "`return`"))
+                .or(ParseFailure::new(")", "`continue`"))
+                .or(ParseFailure::new(")", "identifier"))
+                .or(ParseFailure::new(")", "`|`"))
+                .or(ParseFailure::new(")", "`[`"))
+                .or(ParseFailure::new(")", "`{`"))
+                .or(ParseFailure::new(")", "`(`"))),
+        );
+    }
+
+    #[test]
+    fn eval_rem() {
+        assert_eq!(
+            Expression::Operation {
+                lhs: Box::new(Expression::Number(Number(17))),
+                rhs: Box::new(Expression::Number(Number(5))),
+                op: Operator::Rem,
+            }
+            .eval(&Env::default()),
+            Ok(Value::Number(2)),
+        );
+    }
+
+    #[test]
+    fn parse_rem_with_mul_precedence() {
+        let (_, expression) = Expression::new("1 + 7 % 4 * 2").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Number(7)));
+    }
+
+    #[test]
+    fn eval_div_by_zero() {
+        assert_eq!(
+            Expression::Operation {
+                lhs: Box::new(Expression::Number(Number(1))),
+                rhs: Box::new(Expression::Number(Number(0))),
+                op: Operator::Div,
+            }
+            .eval(&Env::default()),
+            Err(RuntimeErrorKind::DivisionByZero.into()),
+        );
+    }
+
+    #[test]
+    fn eval_rem_by_zero() {
+        assert_eq!(
+            Expression::Operation {
+                lhs: Box::new(Expression::Number(Number(1))),
+                rhs: Box::new(Expression::Number(Number(0))),
+                op: Operator::Rem,
+            }
+            .eval(&Env::default()),
+            Err(RuntimeErrorKind::DivisionByZero.into()),
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn eval_add_overflow() {
+        assert_eq!(
+            Expression::Operation {
+                lhs: Box::new(Expression::Number(Number(i64::MAX))),
+                rhs: Box::new(Expression::Number(Number(1))),
+                op: Operator::Add,
+            }
+            .eval(&Env::default()),
+            Err(RuntimeErrorKind::Overflow.into()),
+        );
+    }
+
+    #[test]
+    fn eval_mul_beyond_32_bits() {
+        let (_, expression) = Expression::new("65536 * 65536").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Ok(Value::Number(4294967296))
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn eval_mul_overflow() {
+        let (_, expression) = Expression::new("4294967296 * 4294967296").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::Overflow.into()),
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn eval_div_overflow() {
+        assert_eq!(
+            Expression::Operation {
+                lhs: Box::new(Expression::Number(Number(i64::MIN))),
+                rhs: Box::new(Expression::Number(Number(-1))),
+                op: Operator::Div,
+            }
+            .eval(&Env::default()),
+            Err(RuntimeErrorKind::Overflow.into()),
+        );
+    }
+
+    #[test]
+    fn parse_negative_number_as_expr() {
+        assert_eq!(
+            Expression::new("-5"),
+            Ok(("", Expression::Number(Number(-5))))
+        );
+    }
+
+    #[test]
+    fn parse_unary_minus() {
+        assert_eq!(
+            Expression::new("-x"),
+            Ok((
+                "",
+                Expression::Unary {
+                    op: UnaryOperator::Neg,
+                    operand: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "x".to_string(),
+                    })),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_unary_minus_binds_tighter_than_mul() {
+        assert_eq!(
+            Expression::new("-x * 2"),
+            Ok((
+                "",
+                Expression::Operation {
+                    lhs: Box::new(Expression::Unary {
+                        op: UnaryOperator::Neg,
+                        operand: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "x".to_string(),
+                        })),
+                    }),
+                    rhs: Box::new(Expression::Number(Number(2))),
+                    op: Operator::Mul,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_sub_of_negative_number() {
+        assert_eq!(
+            Expression::new("1 - -2"),
+            Ok((
+                "",
+                Expression::Operation {
+                    lhs: Box::new(Expression::Number(Number(1))),
+                    rhs: Box::new(Expression::Number(Number(-2))),
+                    op: Operator::Sub,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_unary_operators() {
+        let (_, expression) = Expression::new("-(2 + 3) * +4 - -1").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Number(-19)));
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn eval_neg_overflow() {
+        let (_, expression) = Expression::new("-(-9223372036854775808)").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::Overflow.into()),
+        );
+    }
+
+    #[test]
+    fn parse_bools() {
+        assert_eq!(Expression::new("true"), Ok(("", Expression::Bool(true))));
+        assert_eq!(Expression::new("false"), Ok(("", Expression::Bool(false))));
+    }
+
+    #[test]
+    fn parse_identifier_starting_with_bool() {
+        assert_eq!(
+            Expression::new("trueish"),
+            Ok((
+                "",
+                Expression::BindingUsage(BindingUsage {
+                    name: "trueish".to_string(),
+                })
+            ))
+        );
+    }
+
+    #[test]
+    fn parse_comparison_ops() {
+        assert_eq!(Operator::new("=="), Ok(("", Operator::Eq)));
+        assert_eq!(Operator::new("!="), Ok(("", Operator::Ne)));
+        assert_eq!(Operator::new("<"), Ok(("", Operator::Lt)));
+        assert_eq!(Operator::new("<="), Ok(("", Operator::Le)));
+        assert_eq!(Operator::new(">"), Ok(("", Operator::Gt)));
+        assert_eq!(Operator::new(">="), Ok(("", Operator::Ge)));
+    }
+
+    #[test]
+    fn parse_logical_ops() {
+        assert_eq!(Operator::new("&&"), Ok(("", Operator::And)));
+        assert_eq!(Operator::new("||"), Ok(("", Operator::Or)));
+        assert_eq!(UnaryOperator::new("!"), Ok(("", UnaryOperator::Not)));
+    }
+
+    #[test]
+    fn parse_logical_ops_with_precedence() {
+        assert_eq!(
+            Expression::new("a || b && 1 < 2"),
+            Ok((
+                "",
+                Expression::Operation {
+                    lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "a".to_string(),
+                    })),
+                    rhs: Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "b".to_string(),
+                        })),
+                        rhs: Box::new(Expression::Operation {
+                            lhs: Box::new(Expression::Number(Number(1))),
+                            rhs: Box::new(Expression::Number(Number(2))),
+                            op: Operator::Lt,
+                        }),
+                        op: Operator::And,
+                    }),
+                    op: Operator::Or,
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn eval_comparisons() {
+        let (_, expression) =
+            Expression::new("1 + 1 == 2 && 3 != 4 && 2 <= 2 && !(5 < 4)").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_bool_equality() {
+        let (_, expression) = Expression::new("true == (1 > 2)").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn eval_and_short_circuits() {
+        let (_, expression) = Expression::new("false && undefined").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Bool(false)));
+    }
+
+    #[test]
+    fn eval_or_short_circuits() {
+        let (_, expression) = Expression::new("true || 1 / 0 == 0").unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_add_bool_and_number() {
+        let (_, expression) = Expression::new("true + 1").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::TypeError("cannot apply `+` to true and 1".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn eval_compare_bool_and_number() {
+        let (_, expression) = Expression::new("1 == true").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::TypeError("cannot apply `==` to 1 and true".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn eval_order_bool_and_number() {
+        let (_, expression) = Expression::new("2 <= false || 1").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::TypeError("cannot apply `<=` to 2 and false".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn eval_not_number() {
+        let (_, expression) = Expression::new("!1").unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::TypeError("cannot apply `!` to 1".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn parse_string() {
+        use crate::string_literal::StringPart;
+
+        assert_eq!(
+            Expression::new(r#""a\tb" "#),
+            Ok((
+                " ",
+                Expression::String(StringLiteral {
+                    parts: vec![StringPart::Text("a\tb".to_string())],
+                }),
+            )),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_unterminated_string() {
+        assert_eq!(
+            Expression::new("1 + \"abc"),
+            Err(ParseFailure::new("", "`\"` to end the string")),
+        );
+    }
+
+    #[test]
+    fn eval_string_concatenation() {
+        let (_, expression) = Expression::new(r#""foo" + "bar" + """#).unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Ok(Value::String("foobar".to_string())),
+        );
+    }
+
+    #[test]
+    fn eval_string_equality() {
+        let (_, expression) = Expression::new(r#""a" == "a" && "a" != "b""#).unwrap();
+        assert_eq!(expression.eval(&Env::default()), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_add_string_and_number() {
+        let (_, expression) = Expression::new(r#""a" + 1"#).unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(RuntimeErrorKind::TypeError("cannot apply `+` to \"a\" and 1".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn eval_sub_strings() {
+        let (_, expression) = Expression::new(r#""a" - "b""#).unwrap();
+        assert_eq!(
+            expression.eval(&Env::default()),
+            Err(
+                RuntimeErrorKind::TypeError("cannot apply `-` to \"a\" and \"b\"".to_string())
+                    .into()
+            ),
+        );
+    }
+
+    #[test]
+    fn parse_float() {
+        assert_eq!(
+            Expression::new("2.75"),
+            Ok(("", Expression::Float(Float(2.75))))
+        );
+    }
+
+    #[test]
+    fn parse_float_with_exponent() {
+        assert_eq!(
+            Expression::new("-1e-3"),
+            Ok(("", Expression::Float(Float(-0.001))))
+        );
+    }
+
+    #[test]
+    fn cannot_parse_float_that_overflows() {
+        assert_eq!(
+            Expression::new("1e999"),
+            Err(ParseFailure::new("1e999", "float within range")),
+        );
+    }
+
+    fn eval_str(s: &str) -> Result<Value, ControlFlow> {
+        let (_, expression) = Expression::new(s).unwrap();
+        expression.eval(&Env::default())
+    }
+
+    #[test]
+    fn eval_integer_division_stays_integer() {
+        assert_eq!(eval_str("7 / 2"), Ok(Value::Number(3)));
+    }
+
+    #[test]
+    fn eval_float_division() {
+        assert_eq!(eval_str("7.0 / 2"), Ok(Value::Float(3.5)));
+    }
+
+    #[test]
+    fn eval_mixed_arithmetic_promotes_to_float() {
+        assert_eq!(eval_str("1 + 0.5 * 2"), Ok(Value::Float(2.0)));
+    }
+
+    #[test]
+    fn eval_mixed_comparison() {
+        assert_eq!(eval_str("1 == 1.0 && 2 > 1.5"), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_negated_float() {
+        assert_eq!(eval_str("-(2.5)"), Ok(Value::Float(-2.5)));
+    }
+
+    #[test]
+    fn eval_float_division_by_zero() {
+        assert_eq!(
+            eval_str("1.5 / 0"),
+            Err(RuntimeErrorKind::DivisionByZero.into())
+        );
+    }
+
+    #[test]
+    fn eval_conversion_functions() {
+        assert_eq!(eval_str("int(9.99) + int(2)"), Ok(Value::Number(11)));
+        assert_eq!(eval_str("float(7) / 2"), Ok(Value::Float(3.5)));
+    }
+
+    #[test]
+    fn parse_range_binds_loosest() {
+        assert_eq!(
+            Expression::new("0..n + 1"),
+            Ok((
+                "",
+                Expression::Range {
+                    start: Box::new(Expression::Number(Number(0))),
+                    end: Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "n".to_string(),
+                        })),
+                        rhs: Box::new(Expression::Number(Number(1))),
+                        op: Operator::Add,
+                    }),
+                    inclusive: false,
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn eval_range_values() {
+        assert_eq!(
+            eval_str("1..=3"),
+            Ok(Value::Range(Range {
+                start: 1,
+                end: 3,
+                inclusive: true,
+            })),
+        );
+        assert_eq!(
+            eval_str("2 - 2..3").map(|v| v.to_string()),
+            Ok("0..3".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_range_with_non_number_bound() {
+        assert_eq!(
+            eval_str("0..true"),
+            Err(RuntimeErrorKind::TypeError(
+                "range bounds must be numbers, found true".to_string()
+            )
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_slice_with_ranges() {
+        assert_eq!(
+            eval_str("[1, 2, 3, 4][1..=2] + { let r = 2..4\n [5, 6, 7, 8][r] }"),
+            Ok(Value::list(
+                [2, 3, 7, 8].iter().copied().map(Value::Number).collect()
+            )),
+        );
+    }
 }
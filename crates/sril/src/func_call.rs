@@ -0,0 +1,453 @@
+use std::rc::Rc;
+
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::RuntimeErrorKind;
+use crate::expression::Expression;
+use crate::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub struct FuncCall {
+    pub callee: Box<Expression>,
+    pub args: Vec<Expression>,
+}
+
+impl FuncCall {
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let callee = self.callee.eval(env)?;
+        let args = self.eval_args(env)?;
+
+        call(callee, args)
+    }
+
+    /// Evaluates the call as the new value of the binding `name`.
+    ///
+    /// In `xs = push(xs, item)` the binding would still share the list with
+    /// the argument, so `push` would have to copy it and building up a list
+    /// that way would take quadratic time. Instead, the binding gives up its
+    /// value for the duration of the call, so that `push` or `insert` can
+    /// change it in place. The arguments are evaluated first as usual, and
+    /// the binding gets its value back if the call fails.
+    pub(crate) fn eval_replacing(&self, env: &Env, name: &str) -> Result<Value, ControlFlow> {
+        let callee = self.callee.eval(env)?;
+        let mut args = self.eval_args(env)?;
+
+        let builtin = match (callee, self.args.first()) {
+            (Value::Builtin(builtin), Some(Expression::BindingUsage(target)))
+                if builtin.is_update() && target.name == name =>
+            {
+                builtin
+            }
+            (callee, _) => return call(callee, args),
+        };
+        let mut target = args.remove(0);
+
+        // Evaluating the other arguments may have assigned a different value
+        // to the binding, which then keeps it.
+        let previous = env.replace_binding(name, Value::Unit)?;
+        let shared = shares(&previous, &target);
+        if shared {
+            drop(previous);
+        } else {
+            env.assign_binding(name, previous)?;
+        }
+
+        match builtin.update(&mut target, args) {
+            Ok(()) => Ok(target),
+            Err(err) => {
+                if shared {
+                    env.assign_binding(name, target)?;
+                }
+                Err(err.into())
+            }
+        }
+    }
+
+    fn eval_args(&self, env: &Env) -> Result<Vec<Value>, ControlFlow> {
+        self.args.iter().map(|arg| arg.eval(env)).collect()
+    }
+}
+
+fn call(callee: Value, args: Vec<Value>) -> Result<Value, ControlFlow> {
+    let result = match callee {
+        Value::Function(function) => function.call(args),
+        Value::Closure(closure) => closure.call(args),
+        Value::Builtin(builtin) => builtin.call(args),
+        callee => Err(RuntimeErrorKind::TypeError(format!(
+            "cannot call {}, it is not a function",
+            callee
+        ))
+        .into()),
+    };
+
+    result.map_err(ControlFlow::from)
+}
+
+/// Whether `a` and `b` are the same list or map, rather than equal ones.
+fn shares(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::List(a), Value::List(b)) => Rc::ptr_eq(a, b),
+        (Value::Map(a), Value::Map(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding_usage::BindingUsage;
+    use crate::block::Block;
+    use crate::expression::{Number, Operator};
+
+    #[test]
+    fn parse_func_call() {
+        assert_eq!(
+            Expression::new("add(1, 2 * 3)"),
+            Ok((
+                "",
+                Expression::FuncCall(FuncCall {
+                    callee: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "add".to_string(),
+                    })),
+                    args: vec![
+                        Expression::Number(Number(1)),
+                        Expression::Operation {
+                            lhs: Box::new(Expression::Number(Number(2))),
+                            rhs: Box::new(Expression::Number(Number(3))),
+                            op: Operator::Mul,
+                        },
+                    ],
+                }),
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_chained_func_calls() {
+        assert_eq!(
+            Expression::new("f()(1)"),
+            Ok((
+                "",
+                Expression::FuncCall(FuncCall {
+                    callee: Box::new(Expression::FuncCall(FuncCall {
+                        callee: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "f".to_string(),
+                        })),
+                        args: Vec::new(),
+                    })),
+                    args: vec![Expression::Number(Number(1))],
+                }),
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_call_binds_tighter_than_unary_minus() {
+        assert!(matches!(
+            Expression::new("-f(1)"),
+            Ok(("", Expression::Unary { ref operand, .. }))
+                if matches!(**operand, Expression::FuncCall(_)),
+        ));
+    }
+
+    #[test]
+    fn do_not_parse_call_across_whitespace() {
+        assert_eq!(
+            Expression::new("f (1)"),
+            Ok((
+                " (1)",
+                Expression::BindingUsage(BindingUsage {
+                    name: "f".to_string(),
+                }),
+            )),
+        );
+    }
+
+    #[test]
+    fn eval_func_call() {
+        let (_, block) = Block::new(
+            "{
+                fn square(x) { x * x }
+                square(square(3)) + 1
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(82)));
+    }
+
+    #[test]
+    fn eval_early_return() {
+        let (_, block) = Block::new(
+            "{
+                fn sign(x) {
+                    if x < 0 { return -1 }
+                    if x == 0 { return 0 }
+                    1
+                }
+                sign(-5) * 100 + sign(0) * 10 + sign(7)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(-99)));
+    }
+
+    #[test]
+    fn eval_return_from_inside_loop() {
+        let (_, block) = Block::new(
+            "{
+                fn firstmultiple(n) {
+                    let mut i = 1
+                    loop {
+                        if i % n == 0 { return i }
+                        i += 1
+                    }
+                }
+                firstmultiple(7)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(7)));
+    }
+
+    #[test]
+    fn eval_args_in_fresh_scope() {
+        let (_, block) = Block::new(
+            "{
+                let x = 1
+                fn shadow(x) { x }
+                shadow(2) + x
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(3)));
+    }
+
+    #[test]
+    fn eval_function_passed_as_value() {
+        let (_, block) = Block::new(
+            "{
+                fn twice(f, x) { f(f(x)) }
+                fn inc(x) { x + 1 }
+                twice(inc, 5)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(7)));
+    }
+
+    #[test]
+    fn eval_call_with_wrong_arity() {
+        let (_, block) = Block::new("{ fn f(a, b) { a }; f(1) }").unwrap();
+
+        assert_eq!(
+            block.eval(&Env::default()),
+            Err(RuntimeErrorKind::ArityMismatch {
+                name: "f".to_string(),
+                expected: 2,
+                found: 1,
+            }
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_call_of_non_function() {
+        let (_, block) = Block::new("{ let f = 1; f() }").unwrap();
+
+        assert_eq!(
+            block.eval(&Env::default()),
+            Err(
+                RuntimeErrorKind::TypeError("cannot call 1, it is not a function".to_string())
+                    .into()
+            ),
+        );
+    }
+
+    #[test]
+    fn eval_break_escaping_function() {
+        let (_, block) = Block::new("{ fn f() { break }; loop { f() } }").unwrap();
+
+        assert_eq!(
+            block.eval(&Env::default()),
+            Err(RuntimeErrorKind::BreakOutsideLoop.into()),
+        );
+    }
+
+    #[test]
+    fn eval_recursive_function() {
+        let (_, block) = Block::new(
+            "{
+                fn fact(n) { if n <= 1 { 1 } else { n * fact(n - 1) } }
+                fact(10)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(3628800)));
+    }
+
+    #[test]
+    fn eval_mutually_recursive_functions() {
+        let (_, block) = Block::new(
+            "{
+                fn even(n) { if n == 0 { true } else { odd(n - 1) } }
+                fn odd(n) { if n == 0 { false } else { even(n - 1) } }
+                even(10) && odd(7) && !even(3)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Bool(true)));
+    }
+
+    #[test]
+    fn eval_recursion_up_to_max_call_depth() {
+        let env = Env::default();
+        env.set_max_call_depth(50);
+
+        let (_, block) = Block::new(
+            "{
+                fn depth(n) { if n == 0 { 0 } else { 1 + depth(n - 1) } }
+                depth(49)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&env), Ok(Value::Number(49)));
+    }
+
+    #[test]
+    fn eval_recursion_beyond_max_call_depth() {
+        let env = Env::default();
+        env.set_max_call_depth(50);
+
+        let (_, block) = Block::new(
+            "{
+                fn forever(n) { forever(n + 1) }
+                forever(0)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            block.eval(&env),
+            Err(RuntimeErrorKind::StackOverflow { max_depth: 50 }.into()),
+        );
+    }
+
+    #[test]
+    fn eval_default_max_call_depth_without_overflowing_host_stack() {
+        let (_, block) = Block::new(
+            "{
+                fn forever(n) { if n < 0 { 0 } else { 1 + forever(n + 1) } }
+                forever(0)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(
+            block.eval(&Env::default()),
+            Err(RuntimeErrorKind::StackOverflow {
+                max_depth: crate::env::DEFAULT_MAX_CALL_DEPTH,
+            }
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_nesting_beyond_max_eval_depth() {
+        let env = Env::default();
+        env.set_max_eval_depth(40);
+
+        let (_, block) = Block::new("{ fn f() { {{{{{{{{{{ 1 }}}}}}}}}} }; f() + f() }").unwrap();
+        assert_eq!(block.eval(&env), Ok(Value::Number(2)));
+
+        let (_, block) =
+            Block::new("{ fn f() { {{{{{{{{{{ {{{{{{{{{{ 1 }}}}}}}}}} }}}}}}}}}} }; f() }")
+                .unwrap();
+        assert_eq!(
+            block.eval(&env),
+            Err(RuntimeErrorKind::NestingTooDeep { max_depth: 40 }.into()),
+        );
+    }
+
+    #[test]
+    fn eval_recursion_at_realistic_depth() {
+        let (_, block) = Block::new(
+            "{
+                fn even(n) { if n == 0 { true } else { odd(n - 1) } }
+                fn odd(n) { if n == 0 { false } else { even(n - 1) } }
+                even(3000) && odd(3001)
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Bool(true)));
+    }
+
+    // Spawned threads get 2 MiB of stack by default, against the 8 MiB of the
+    // main thread, and a debug build uses far more than that at the default
+    // limits. The stack is grown as needed instead.
+    #[test]
+    fn eval_deep_recursion_without_overflowing_small_host_stack() {
+        let sources = [
+            "fn f(n) {
+                if n == 0 { 0 } else { {{{{{{{{{{ {{{{{{{{{{ 1 + f(n - 1) }}}}}}}}}} }}}}}}}}}} }
+            }
+            f(500)",
+            "fn f(n) {
+                loop { break while true { return { (|| { 1 + f(n - 1) })() } } }
+            }
+            f(99)",
+        ];
+
+        let results: Vec<_> = sources
+            .into_iter()
+            .map(|source| {
+                std::thread::Builder::new()
+                    .stack_size(2 * 1024 * 1024)
+                    .spawn(move || {
+                        crate::parse_program(source)
+                            .unwrap()
+                            .eval(&Env::default())
+                            .map(|value| value.to_string())
+                            .map_err(|err| err.to_string())
+                    })
+                    .unwrap()
+                    .join()
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(results[0], Ok("500".to_string()));
+        assert!(
+            results[1].as_ref().unwrap_err().contains("stack overflow"),
+            "{:?}",
+            results[1],
+        );
+    }
+
+    #[test]
+    fn eval_call_depth_is_released_after_error() {
+        let env = Env::default();
+        env.set_max_call_depth(10);
+
+        let (_, block) = Block::new(
+            "{
+                fn forever() { forever() }
+                fn fine() { 1 }
+                forever()
+            }",
+        )
+        .unwrap();
+        assert!(block.eval(&env).is_err());
+
+        let (_, block) = Block::new("{ fn fine() { 1 }; fine() }").unwrap();
+        assert_eq!(block.eval(&env), Ok(Value::Number(1)));
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use crate::binding_def::BindingKind;
+use crate::block::Block;
+use crate::control_flow::ControlFlow;
+use crate::env::{Env, ScopeRef};
+use crate::error::{ParseFailure, RuntimeError, RuntimeErrorKind, Source};
+use crate::expression::Expression;
+use crate::utils;
+use crate::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub struct FuncDef {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Rc<Block>,
+}
+
+impl FuncDef {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_keyword("fn", s)?;
+        let (s, _) = utils::extract_required_whitespaces(s)?;
+
+        let (s, name) = utils::extract_identifier(s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, params) = utils::extract_params("(", ")", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, body) = utils::extract_nested(Block::new, s)?;
+
+        Ok((
+            s,
+            Self {
+                name: name.to_string(),
+                params,
+                body: Rc::new(body),
+            },
+        ))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let function = Function {
+            name: self.name.clone(),
+            params: self.params.clone(),
+            body: Rc::clone(&self.body),
+            captured: Captured::new(env),
+        };
+
+        env.store_binding(
+            self.name.clone(),
+            Value::Function(Rc::new(function)),
+            BindingKind::Let,
+        )?;
+
+        Ok(Value::Unit)
+    }
+}
+
+/// What a function or closure keeps of the place it was defined in: the
+/// scope its body looks up everything that is not a parameter in, and the
+/// code it was defined in, against which errors in its body are reported.
+pub(crate) struct Captured {
+    scope: ScopeRef,
+    source: Option<Arc<Source>>,
+}
+
+impl Captured {
+    pub(crate) fn new(env: &Env) -> Self {
+        Self {
+            scope: env.capture(),
+            source: env.source(),
+        }
+    }
+
+    pub(crate) fn scope(&self) -> &ScopeRef {
+        &self.scope
+    }
+
+    /// Calls the function or closure called `name` that was defined here: its
+    /// `params` are bound to `args` in a new child of the captured scope, in
+    /// which `eval_body` then evaluates the body.
+    pub(crate) fn call(
+        &self,
+        name: &str,
+        params: &[String],
+        args: Vec<Value>,
+        eval_body: impl FnOnce(&Env) -> Result<Value, ControlFlow>,
+    ) -> Result<Value, RuntimeError> {
+        if args.len() != params.len() {
+            return Err(RuntimeErrorKind::ArityMismatch {
+                name: name.to_string(),
+                expected: params.len(),
+                found: args.len(),
+            }
+            .into());
+        }
+
+        let captured = self
+            .scope
+            .env()
+            .ok_or_else(|| RuntimeErrorKind::EnvDropped(name.to_string()))?;
+        let _call = captured.enter_call()?;
+        let _source = captured.enter_source(self.source.clone());
+
+        let env = captured.create_child();
+        for (param, arg) in params.iter().zip(args) {
+            env.store_binding(param.clone(), arg, BindingKind::Let)?;
+        }
+
+        match eval_body(&env) {
+            Ok(value) | Err(ControlFlow::Return(value)) => Ok(value),
+            Err(flow) => Err(flow
+                .into_runtime_error()
+                .resolve_location(self.source.as_ref())),
+        }
+    }
+}
+
+/// Two functions or closures with the same body are only equal if they were
+/// also defined in the same scope.
+impl PartialEq for Captured {
+    fn eq(&self, other: &Self) -> bool {
+        self.scope.ptr_eq(&other.scope)
+    }
+}
+
+/// A function value: a `FuncDef` together with the scope it was defined in,
+/// which is where its body looks up everything that is not a parameter.
+pub struct Function {
+    pub name: String,
+    pub params: Vec<String>,
+    pub body: Rc<Block>,
+    pub(crate) captured: Captured,
+}
+
+impl Function {
+    pub(crate) fn call(&self, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        self.captured
+            .call(&self.name, &self.params, args, |env| self.body.eval(env))
+    }
+}
+
+impl fmt::Debug for Function {
+    // The captured scope usually contains the function itself, so it is left
+    // out to keep the output finite.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Function")
+            .field("name", &self.name)
+            .field("params", &self.params)
+            .field("body", &self.body)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Two function values are only equal if they come from the same definition,
+/// evaluated in the same scope.
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.body, &other.body) && self.captured == other.captured
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Return {
+    pub value: Option<Box<Expression>>,
+}
+
+impl Return {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_keyword("return", s)?;
+
+        // Like `break`, the value has to start on the same line.
+        let (after_spaces, _) = utils::extract_inline_whitespaces(s);
+
+        match Expression::new(after_spaces) {
+            Ok((s, value)) => Ok((
+                s,
+                Self {
+                    value: Some(Box::new(value)),
+                },
+            )),
+            Err(_) => Ok((s, Self { value: None })),
+        }
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let value = match &self.value {
+            Some(value) => value.eval(env)?,
+            None => Value::Unit,
+        };
+
+        Err(ControlFlow::Return(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding_usage::BindingUsage;
+    use crate::expression::Operator;
+    use crate::statement::Statement;
+
+    #[test]
+    fn parse_func_def() {
+        assert_eq!(
+            FuncDef::new("fn add(a, b) { a + b }"),
+            Ok((
+                "",
+                FuncDef {
+                    name: "add".to_string(),
+                    params: vec!["a".to_string(), "b".to_string()],
+                    body: Rc::new(Block {
+                        statements: vec![Statement::Expression(Expression::Operation {
+                            lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                                name: "a".to_string(),
+                            })),
+                            rhs: Box::new(Expression::BindingUsage(BindingUsage {
+                                name: "b".to_string(),
+                            })),
+                            op: Operator::Add,
+                        })],
+                        locations: Vec::new(),
+                    }),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_func_def_without_params() {
+        assert_eq!(
+            FuncDef::new("fn nothing() {}"),
+            Ok((
+                "",
+                FuncDef {
+                    name: "nothing".to_string(),
+                    params: Vec::new(),
+                    body: Rc::new(Block {
+                        statements: Vec::new(),
+                        locations: Vec::new(),
+                    }),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_func_def_with_non_identifier_param() {
+        assert_eq!(
+            FuncDef::new("fn f(1) {}"),
+            Err(ParseFailure::new("1) {}", "identifier").or(ParseFailure::new("1) {}", "`)`"))),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_func_def_with_duplicate_params() {
+        assert_eq!(
+            FuncDef::new("fn f(x, x) {}"),
+            Err(ParseFailure::new(
+                "x) {}",
+                "a parameter name other than `x`"
+            )),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_func_defs_nested_beyond_max_depth() {
+        let source = format!("{}{}", "fn f() { ".repeat(1000), "}".repeat(1000));
+        let at_limit = "fn f() ".len() + "fn f() { ".len() * utils::MAX_NESTING_DEPTH;
+
+        assert_eq!(
+            FuncDef::new(&source),
+            Err(ParseFailure::nested_too_deeply(&source[at_limit..])),
+        );
+    }
+
+    #[test]
+    fn parse_return_without_value() {
+        assert_eq!(
+            Return::new("return\n1"),
+            Ok(("\n1", Return { value: None }))
+        );
+    }
+
+    #[test]
+    fn eval_func_def_stores_function() {
+        let env = Env::default();
+        let (_, func_def) = FuncDef::new("fn id(x) { x }").unwrap();
+
+        assert_eq!(func_def.eval(&env), Ok(Value::Unit));
+        assert!(matches!(
+            env.get_binding_value("id"),
+            Ok(Value::Function(function)) if function.params == vec!["x".to_string()],
+        ));
+    }
+
+    #[test]
+    fn call_function() {
+        let env = Env::default();
+        let (_, func_def) = FuncDef::new("fn sub(a, b) { a - b }").unwrap();
+        func_def.eval(&env).unwrap();
+
+        let function = match env.get_binding_value("sub") {
+            Ok(Value::Function(function)) => function,
+            other => panic!("expected a function, got {:?}", other),
+        };
+
+        assert_eq!(
+            function.call(vec![Value::Number(10), Value::Number(3)]),
+            Ok(Value::Number(7)),
+        );
+    }
+
+    #[test]
+    fn call_function_with_wrong_arity() {
+        let env = Env::default();
+        let (_, func_def) = FuncDef::new("fn one(a) { a }").unwrap();
+        func_def.eval(&env).unwrap();
+
+        let function = match env.get_binding_value("one") {
+            Ok(Value::Function(function)) => function,
+            other => panic!("expected a function, got {:?}", other),
+        };
+
+        assert_eq!(
+            function.call(Vec::new()),
+            Err(RuntimeErrorKind::ArityMismatch {
+                name: "one".to_string(),
+                expected: 1,
+                found: 0,
+            }
+            .into()),
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use crate::block::Block;
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::{ParseFailure, RuntimeErrorKind};
+use crate::expression::Expression;
+use crate::utils;
+use crate::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub struct If {
+    pub condition: Box<Expression>,
+    pub then_branch: Block,
+    /// Either a `Block` or, for `else if` chains, another `If`.
+    pub else_branch: Option<Box<Expression>>,
+}
+
+impl If {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_keyword("if", s)?;
+        let (s, _) = utils::extract_required_whitespaces(s)?;
+
+        let (s, condition) = Expression::new(s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, then_branch) = Block::new(s)?;
+
+        let (after_whitespace, _) = utils::extract_whitespaces(s);
+        let after_else = match utils::extract_keyword("else", after_whitespace) {
+            Ok(after_else) => after_else,
+            Err(_) => {
+                return Ok((
+                    s,
+                    Self {
+                        condition: Box::new(condition),
+                        then_branch,
+                        else_branch: None,
+                    },
+                ))
+            }
+        };
+        let (after_else, _) = utils::extract_whitespaces(after_else);
+
+        let (s, else_branch) = Self::new(after_else)
+            .map(|(s, if_expr)| (s, Expression::If(if_expr)))
+            .or_else(|err| {
+                Block::new(after_else)
+                    .map(|(s, block)| (s, Expression::Block(block)))
+                    .map_err(|other| err.or(other))
+            })?;
+
+        Ok((
+            s,
+            Self {
+                condition: Box::new(condition),
+                then_branch,
+                else_branch: Some(Box::new(else_branch)),
+            },
+        ))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        match self.condition.eval(env)? {
+            Value::Bool(true) => self.then_branch.eval(env),
+            Value::Bool(false) => match &self.else_branch {
+                Some(else_branch) => else_branch.eval(env),
+                None => Ok(Value::Unit),
+            },
+            condition => Err(RuntimeErrorKind::TypeError(format!(
+                "if condition must be a boolean, found {}",
+                condition
+            ))
+            .into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding_def::BindingKind;
+    use crate::binding_usage::BindingUsage;
+    use crate::expression::{Number, Operator};
+    use crate::statement::Statement;
+
+    #[test]
+    fn parse_if_without_else() {
+        assert_eq!(
+            If::new("if x { 1 }"),
+            Ok((
+                "",
+                If {
+                    condition: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "x".to_string(),
+                    })),
+                    then_branch: Block {
+                        statements: vec![Statement::Expression(Expression::Number(Number(1)))],
+                        locations: Vec::new(),
+                    },
+                    else_branch: None,
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_if_else() {
+        assert_eq!(
+            If::new("if 1 < 2 { 1 } else { 2 }"),
+            Ok((
+                "",
+                If {
+                    condition: Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::Number(Number(1))),
+                        rhs: Box::new(Expression::Number(Number(2))),
+                        op: Operator::Lt,
+                    }),
+                    then_branch: Block {
+                        statements: vec![Statement::Expression(Expression::Number(Number(1)))],
+                        locations: Vec::new(),
+                    },
+                    else_branch: Some(Box::new(Expression::Block(Block {
+                        statements: vec![Statement::Expression(Expression::Number(Number(2)))],
+                        locations: Vec::new(),
+                    }))),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_else_if_chain() {
+        assert_eq!(
+            If::new("if a { 1 } else if b { 2 } else { 3 }"),
+            Ok((
+                "",
+                If {
+                    condition: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "a".to_string(),
+                    })),
+                    then_branch: Block {
+                        statements: vec![Statement::Expression(Expression::Number(Number(1)))],
+                        locations: Vec::new(),
+                    },
+                    else_branch: Some(Box::new(Expression::If(If {
+                        condition: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "b".to_string(),
+                        })),
+                        then_branch: Block {
+                            statements: vec![Statement::Expression(Expression::Number(Number(2)))],
+                            locations: Vec::new(),
+                        },
+                        else_branch: Some(Box::new(Expression::Block(Block {
+                            statements: vec![Statement::Expression(Expression::Number(Number(3)))],
+                            locations: Vec::new(),
+                        }))),
+                    }))),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn do_not_consume_whitespace_without_else() {
+        assert_eq!(
+            If::new("if true {}\nelsewhere").map(|(s, _)| s),
+            Ok("\nelsewhere"),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_else_without_block() {
+        assert_eq!(
+            If::new("if true { 1 } else 2").map(|(s, _)| s),
+            Err(ParseFailure::new("2", "`if`").or(ParseFailure::new("2", "`{`"))),
+        );
+    }
+
+    #[test]
+    fn eval_taken_branch() {
+        let (_, if_expr) = If::new("if 2 > 1 { 10 } else { 20 }").unwrap();
+        assert_eq!(if_expr.eval(&Env::default()), Ok(Value::Number(10)));
+    }
+
+    #[test]
+    fn eval_else_branch() {
+        let (_, if_expr) = If::new("if 2 < 1 { 10 } else { 20 }").unwrap();
+        assert_eq!(if_expr.eval(&Env::default()), Ok(Value::Number(20)));
+    }
+
+    #[test]
+    fn eval_else_if_chain() {
+        let env = Env::default();
+        env.store_binding("n".to_string(), Value::Number(0), BindingKind::Let)
+            .unwrap();
+
+        let (_, if_expr) = If::new("if n < 0 { -1 } else if n == 0 { 0 } else { 1 }").unwrap();
+        assert_eq!(if_expr.eval(&env), Ok(Value::Number(0)));
+    }
+
+    #[test]
+    fn eval_untaken_if_without_else() {
+        let (_, if_expr) = If::new("if false { 10 }").unwrap();
+        assert_eq!(if_expr.eval(&Env::default()), Ok(Value::Unit));
+    }
+
+    #[test]
+    fn eval_non_bool_condition() {
+        let (_, if_expr) = If::new("if 1 { 10 }").unwrap();
+        assert_eq!(
+            if_expr.eval(&Env::default()),
+            Err(
+                RuntimeErrorKind::TypeError("if condition must be a boolean, found 1".to_string())
+                    .into()
+            ),
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use crate::binding_def::BindingKind;
+use crate::env::Env;
+use crate::error::{Error, RuntimeError};
+use crate::value::Value;
+use crate::Program;
+
+/// Runs sril code on behalf of an application embedding it.
+///
+/// An interpreter keeps a single global environment across evaluations, so
+/// the host can provide inputs with [`set_global`](Self::set_global), evaluate
+/// code that uses them and read the results back by name. Rust values are
+/// converted to and from [`Value`] with `From` and `TryFrom`.
+///
+/// ```
+/// use sril::Interpreter;
+///
+/// let interpreter = Interpreter::new();
+/// interpreter.set_global("order", vec![12, 30]).unwrap();
+/// interpreter
+///     .eval_str("let total = order[0] + order[1]\nlet free = total >= 40")
+///     .unwrap();
+///
+/// assert_eq!(interpreter.get_global::<i32>("total"), Ok(42));
+/// assert_eq!(interpreter.get_global::<bool>("free"), Ok(true));
+/// ```
+#[derive(Debug, Default)]
+pub struct Interpreter {
+    env: Env,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The global environment that everything is evaluated in.
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    pub fn max_call_depth(&self) -> usize {
+        self.env.max_call_depth()
+    }
+
+    /// Limits how deeply function calls may nest, see
+    /// [`Env::set_max_call_depth`].
+    pub fn set_max_call_depth(&self, max: usize) {
+        self.env.set_max_call_depth(max);
+    }
+
+    pub fn max_eval_depth(&self) -> usize {
+        self.env.max_eval_depth()
+    }
+
+    /// Limits how deeply expressions and blocks may nest, see
+    /// [`Env::set_max_eval_depth`].
+    pub fn set_max_eval_depth(&self, max: usize) {
+        self.env.set_max_eval_depth(max);
+    }
+
+    /// Binds `name` to `value` in the global environment, replacing any
+    /// earlier global of that name. The binding is mutable, so scripts can
+    /// assign to it. Fails if `name` was already declared `const`.
+    pub fn set_global(&self, name: &str, value: impl Into<Value>) -> Result<(), Error> {
+        Ok(self
+            .env
+            .store_binding(name.to_string(), value.into(), BindingKind::LetMut)?)
+    }
+
+    /// Looks up the global called `name` and converts it to `T`, which can be
+    /// `Value` itself to get it unconverted.
+    pub fn get_global<T>(&self, name: &str) -> Result<T, Error>
+    where
+        T: TryFrom<Value>,
+        RuntimeError: From<T::Error>,
+    {
+        let value = self.env.get_binding_value(name)?;
+        Ok(T::try_from(value).map_err(RuntimeError::from)?)
+    }
+
+    /// Parses `source` as a program and evaluates it, returning the value of
+    /// its last statement.
+    pub fn eval_str(&self, source: &str) -> Result<Value, Error> {
+        self.eval_program(&crate::parse_program(source)?)
+    }
+
+    /// Evaluates an already parsed program, so that one that is run many
+    /// times only has to be parsed once.
+    pub fn eval_program(&self, program: &Program) -> Result<Value, Error> {
+        program.eval(&self.env)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+    use std::collections::HashMap;
+    use std::rc::{Rc, Weak};
+
+    use super::*;
+    use crate::error::RuntimeErrorKind;
+
+    #[test]
+    fn eval_with_globals_from_host() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .set_global(
+                "limits",
+                HashMap::from([("max".to_string(), 10), ("min".to_string(), 2)]),
+            )
+            .unwrap();
+        interpreter.set_global("amount", 12).unwrap();
+
+        assert_eq!(
+            interpreter.eval_str("amount >= limits.min && amount <= limits.max"),
+            Ok(Value::Bool(false)),
+        );
+    }
+
+    #[test]
+    fn read_globals_defined_by_script() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval_str("let names = [\"a\", \"b\"]\nlet mut count = len(names)")
+            .unwrap();
+
+        assert_eq!(
+            interpreter.get_global::<Vec<String>>("names"),
+            Ok(vec!["a".to_string(), "b".to_string()]),
+        );
+        assert_eq!(
+            interpreter.get_global::<Value>("count"),
+            Ok(Value::Number(2))
+        );
+    }
+
+    #[test]
+    fn eval_parsed_program_repeatedly() {
+        let interpreter = Interpreter::new();
+        let program = crate::parse_program("counter = counter + 1").unwrap();
+        interpreter.set_global("counter", 0).unwrap();
+
+        for _ in 0..3 {
+            interpreter.eval_program(&program).unwrap();
+        }
+
+        assert_eq!(interpreter.get_global::<i32>("counter"), Ok(3));
+    }
+
+    #[test]
+    fn cannot_get_undefined_global() {
+        assert_eq!(
+            Interpreter::new().get_global::<i32>("missing"),
+            Err(Error::Runtime(
+                RuntimeErrorKind::UndefinedBinding("missing".to_string()).into()
+            )),
+        );
+    }
+
+    #[test]
+    fn cannot_get_global_of_wrong_type() {
+        let interpreter = Interpreter::new();
+        interpreter.set_global("flag", true).unwrap();
+
+        assert_eq!(
+            interpreter.get_global::<String>("flag"),
+            Err(Error::Runtime(
+                RuntimeErrorKind::TypeError("expected a string, found true".to_string()).into()
+            )),
+        );
+    }
+
+    #[test]
+    fn cannot_replace_const_global() {
+        let interpreter = Interpreter::new();
+        interpreter.eval_str("const rate = 3").unwrap();
+
+        assert_eq!(
+            interpreter.set_global("rate", 4),
+            Err(Error::Runtime(
+                RuntimeErrorKind::ConstRedefinition("rate".to_string()).into()
+            )),
+        );
+    }
+
+    #[test]
+    fn limit_call_depth() {
+        let interpreter = Interpreter::new();
+        interpreter.set_max_call_depth(5);
+
+        assert_eq!(interpreter.max_call_depth(), 5);
+        assert_eq!(
+            interpreter
+                .eval_str("fn down(n) { if n == 0 { 0 } else { down(n - 1) } }\ndown(10)")
+                .map_err(|err| match err {
+                    Error::Runtime(err) => err.kind,
+                    err => panic!("unexpected error: {}", err),
+                }),
+            Err(RuntimeErrorKind::StackOverflow { max_depth: 5 }),
+        );
+    }
+
+    /// Weak handles on the functions and closures in `value`.
+    fn callables(value: &Value) -> Vec<Weak<dyn Any>> {
+        match value {
+            Value::Function(function) => vec![Rc::downgrade(&(Rc::clone(function) as Rc<dyn Any>))],
+            Value::Closure(closure) => vec![Rc::downgrade(&(Rc::clone(closure) as Rc<dyn Any>))],
+            Value::List(list) => list.iter().flat_map(callables).collect(),
+            Value::Map(map) => map.values().flat_map(callables).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn free_interpreter_holding_functions() {
+        let interpreter = Interpreter::new();
+        interpreter
+            .eval_str(
+                "fn double(x) { x * 2 }
+                let triple = |x| x * 3
+                let list = [|x| x]
+                let map = { f: |x| x }
+                let mut assigned = 0
+                {
+                    let y = 1
+                    assigned = |x| x + y
+                }
+                fn outer() { fn inner() { 1 }; inner }
+                let h = outer()
+                let mut pushed = []
+                for i in 0..3 { pushed = push(pushed, |x| x + i) }",
+            )
+            .unwrap();
+        assert_eq!(
+            interpreter.eval_str("double(1) + triple(1) + h() + pushed[2](1)"),
+            Ok(Value::Number(9))
+        );
+
+        let names = ["double", "triple", "list", "map", "assigned", "h", "pushed"];
+        let callables: Vec<_> = names
+            .iter()
+            .flat_map(|name| callables(&interpreter.get_global::<Value>(name).unwrap()))
+            .collect();
+        assert_eq!(callables.len(), 9);
+
+        let double = interpreter.get_global::<Value>("double").unwrap();
+        drop(interpreter);
+        drop(double);
+        assert!(callables
+            .iter()
+            .all(|callable| callable.upgrade().is_none()));
+    }
+
+    #[test]
+    fn cannot_call_function_of_dropped_interpreter() {
+        let first = Interpreter::new();
+        first.eval_str("let f = |x| x").unwrap();
+        let f = first.get_global::<Value>("f").unwrap();
+        drop(first);
+
+        let second = Interpreter::new();
+        second.set_global("f", f).unwrap();
+        assert_eq!(
+            second.eval_str("f(1)").map_err(|err| match err {
+                Error::Runtime(err) => err.kind,
+                err => panic!("unexpected error: {}", err),
+            }),
+            Err(RuntimeErrorKind::EnvDropped("<closure>".to_string())),
+        );
+    }
+}
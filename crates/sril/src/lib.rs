@@ -1,28 +1,386 @@
+pub mod assignment;
+#[cfg(feature = "bigint")]
+pub mod bigint;
 pub mod binding_def;
 pub mod binding_usage;
 pub mod block;
+pub mod builtin;
+pub mod closure;
+mod convert;
 pub mod expression;
+pub mod func_call;
+pub mod func_def;
+pub mod if_expr;
+pub mod interpreter;
+pub mod list;
+pub mod loop_expr;
+pub mod map;
 pub mod statement;
+pub mod string_literal;
 mod utils;
 
+mod control_flow;
+
 pub mod env;
+pub mod error;
 pub mod value;
 
+pub use error::Error;
+pub use interpreter::Interpreter;
+
+use std::sync::Arc;
+
+use error::{Source, Span};
+
 #[derive(Debug)]
-pub struct Parse(statement::Statement);
+pub struct Parse {
+    statement: statement::Statement,
+    span: Span,
+    source: Arc<Source>,
+}
 
 impl Parse {
-    pub fn eval(&self, env: &mut env::Env) -> Result<value::Value, String> {
-        self.0.eval(env)
+    pub fn statement(&self) -> &statement::Statement {
+        &self.statement
+    }
+
+    pub fn eval(&self, env: &env::Env) -> Result<value::Value, Error> {
+        let _source = env.enter_source(Some(Arc::clone(&self.source)));
+
+        self.statement.eval(env).map_err(|flow| {
+            flow.into_runtime_error()
+                .resolve_location(Some(&self.source))
+                .with_span_in(self.span, &self.source)
+                .into()
+        })
     }
 }
 
-pub fn parse(s: &str) -> Result<Parse, String> {
-    let (s, stmt) = statement::Statement::new(s)?;
+pub fn parse(s: &str) -> Result<Parse, Error> {
+    let (remainder, statement) =
+        statement::Statement::new(s).map_err(|err| err.into_parse_error(s))?;
+    let (remainder, _) = utils::extract_whitespaces(remainder);
 
-    if s.is_empty() {
-        Ok(Parse(stmt))
+    if remainder.is_empty() {
+        Ok(Parse {
+            statement,
+            span: Span::new(s, 0, s.len()),
+            source: Arc::new(Source {
+                name: None,
+                text: s.to_string(),
+            }),
+        })
     } else {
-        Err("input was not consumed fully by parser".to_string())
+        Err(utils::expected(remainder, "end of input")
+            .into_parse_error(s)
+            .into())
+    }
+}
+
+/// A whole script: statements separated by line breaks and/or `;`.
+#[derive(Debug)]
+pub struct Program {
+    statements: Vec<(statement::Statement, Span)>,
+    source: Arc<Source>,
+}
+
+impl Program {
+    /// Evaluates the statements in order directly in `env`, so that their
+    /// bindings are still there afterwards, and returns the value of the
+    /// last one. An empty program evaluates to `Unit`.
+    ///
+    /// A runtime error points at the innermost statement that failed, which
+    /// may be deep inside a block or in the body of a function.
+    pub fn eval(&self, env: &env::Env) -> Result<value::Value, Error> {
+        let _source = env.enter_source(Some(Arc::clone(&self.source)));
+        let mut value = value::Value::Unit;
+
+        for (statement, span) in &self.statements {
+            value = statement.eval(env).map_err(|flow| {
+                flow.into_runtime_error()
+                    .resolve_location(Some(&self.source))
+                    .with_span_in(*span, &self.source)
+            })?;
+        }
+
+        Ok(value)
+    }
+}
+
+pub fn parse_program(s: &str) -> Result<Program, Error> {
+    parse_source(None, s)
+}
+
+/// Like [`parse_program`], but names where the code came from, such as the
+/// path of a script, for runtime errors to refer to it by.
+pub fn parse_named_program(name: &str, s: &str) -> Result<Program, Error> {
+    parse_source(Some(name.to_string()), s)
+}
+
+fn parse_source(name: Option<String>, s: &str) -> Result<Program, Error> {
+    let (mut remainder, _) =
+        utils::extract_statement_separator(s).unwrap_or_else(|_| utils::extract_whitespaces(s));
+    let mut statements = Vec::new();
+
+    while !remainder.is_empty() {
+        let start = s.len() - remainder.len();
+        let (after_statement, statement) =
+            statement::Statement::new(remainder).map_err(|err| err.into_parse_error(s))?;
+        let end = s.len() - after_statement.len();
+
+        statements.push((statement, Span::new(s, start, end)));
+
+        remainder = match utils::extract_statement_separator(after_statement) {
+            Ok((after_separator, _)) => after_separator,
+            Err(err) => {
+                let (after_whitespace, _) = utils::extract_whitespaces(after_statement);
+                if !after_whitespace.is_empty() {
+                    return Err(err
+                        .or(utils::expected(after_whitespace, "end of input"))
+                        .into_parse_error(s)
+                        .into());
+                }
+                after_whitespace
+            }
+        };
+    }
+
+    Ok(Program {
+        statements,
+        source: Arc::new(Source {
+            name,
+            text: s.to_string(),
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::{ParseError, RuntimeError, RuntimeErrorKind};
+
+    #[test]
+    fn report_position_of_trailing_input() {
+        assert_eq!(
+            parse("1 + 2 )").unwrap_err(),
+            Error::Parse(ParseError {
+                span: Span {
+                    start: 6,
+                    end: 7,
+                    line: 1,
+                    column: 7,
+                },
+                expected: vec!["end of input".to_string()],
+            }),
+        );
+    }
+
+    #[test]
+    fn report_binding_def_error_instead_of_discarding_it() {
+        let err = match parse("{\n  let a = \n}") {
+            Err(Error::Parse(err)) => err,
+            other => panic!("expected a parse error, got {:?}", other),
+        };
+
+        assert_eq!(
+            err.span,
+            Span {
+                start: 13,
+                end: 14,
+                line: 3,
+                column: 1,
+            },
+        );
+        assert!(err.expected.contains(&"number".to_string()));
+    }
+
+    #[test]
+    fn report_position_of_unterminated_string() {
+        assert_eq!(
+            parse("let s = \"abc\ndef").unwrap_err(),
+            Error::Parse(ParseError {
+                span: Span {
+                    start: 16,
+                    end: 16,
+                    line: 2,
+                    column: 4,
+                },
+                expected: vec!["`\"` to end the string".to_string()],
+            }),
+        );
+    }
+
+    #[test]
+    fn parse_with_comments() {
+        let source = "{\r\n  // the answer\r\n  let a = 40 /* almost */ + 2 // done\r\n  a /* /* nested */ */\r\n}";
+        assert_eq!(
+            parse(source).unwrap().eval(&env::Env::default()),
+            Ok(value::Value::Number(42)),
+        );
+    }
+
+    #[test]
+    fn report_unterminated_block_comment() {
+        for source in ["1 /* never closed", "{ 1 /* }"] {
+            assert_eq!(
+                parse(source).unwrap_err(),
+                Error::Parse(ParseError {
+                    span: Span::new(source, source.len(), source.len()),
+                    expected: vec!["`*/` to end the comment".to_string()],
+                }),
+            );
+        }
+    }
+
+    #[test]
+    fn eval_program_in_shared_env() {
+        let env = env::Env::default();
+        let program = parse_program(
+            "// a script\nlet a = 1; let mut b = 2\r\n\nfn double(x) { x * 2 }\nb = double(a + b);;\nb\n",
+        )
+        .unwrap();
+
+        assert_eq!(program.eval(&env), Ok(value::Value::Number(6)));
+        assert_eq!(env.get_binding_value("b"), Ok(value::Value::Number(6)));
+    }
+
+    #[test]
+    fn eval_empty_program() {
+        assert_eq!(
+            parse_program(" \n// nothing\n;")
+                .unwrap()
+                .eval(&env::Env::default()),
+            Ok(value::Value::Unit),
+        );
+    }
+
+    #[test]
+    fn report_missing_statement_separator() {
+        assert_eq!(
+            parse_program("let a = 1 let b = 2").unwrap_err(),
+            Error::Parse(ParseError {
+                span: Span::new("let a = 1 let b = 2", 10, 11),
+                expected: vec!["newline or `;`".to_string(), "end of input".to_string()],
+            }),
+        );
+    }
+
+    #[test]
+    fn report_runtime_error_with_span_of_failing_statement() {
+        assert_eq!(
+            parse_program("let a = 1\nlet b = a / 0\nb")
+                .unwrap()
+                .eval(&env::Env::default()),
+            Err(Error::Runtime(
+                RuntimeError::from(RuntimeErrorKind::DivisionByZero).with_span(Span {
+                    start: 10,
+                    end: 23,
+                    line: 2,
+                    column: 1,
+                })
+            )),
+        );
+    }
+
+    #[test]
+    fn report_runtime_error_at_failing_statement_in_block() {
+        let source = "let a = {\n  let b = 1\n  b / 0\n}";
+
+        assert_eq!(
+            parse_program(source)
+                .unwrap()
+                .eval(&env::Env::default())
+                .unwrap_err()
+                .span(),
+            Some(Span::new(source, 24, 29)),
+        );
+    }
+
+    #[test]
+    fn report_runtime_error_at_failing_statement_in_function_body() {
+        let source = "fn f(x) {\n  let y = x - 1\n  x / y\n}\nf(2)\nf(1)";
+
+        assert_eq!(
+            parse_program(source)
+                .unwrap()
+                .eval(&env::Env::default())
+                .unwrap_err()
+                .span(),
+            Some(Span {
+                start: 28,
+                end: 33,
+                line: 3,
+                column: 3,
+            }),
+        );
+    }
+
+    #[test]
+    fn report_runtime_error_in_function_defined_by_earlier_program() {
+        let env = env::Env::default();
+        let definition = "// divides\nfn f(x) {\n  1 / x\n}";
+        parse_program(definition).unwrap().eval(&env).unwrap();
+
+        assert_eq!(
+            parse_program("f(0)")
+                .unwrap()
+                .eval(&env)
+                .unwrap_err()
+                .span(),
+            Some(Span::new(definition, 23, 28)),
+        );
+    }
+
+    #[test]
+    fn report_source_of_runtime_error_in_function() {
+        let env = env::Env::default();
+        let definition = "fn f(x) { 1 / x }";
+        parse_named_program("lib.sril", definition)
+            .unwrap()
+            .eval(&env)
+            .unwrap();
+
+        match parse_named_program("main.sril", "f(0)").unwrap().eval(&env) {
+            Err(Error::Runtime(err)) => assert_eq!(
+                err.source.as_deref(),
+                Some(&Source {
+                    name: Some("lib.sril".to_string()),
+                    text: definition.to_string(),
+                }),
+            ),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+
+    #[test]
+    fn report_runtime_error_with_statement_span() {
+        assert_eq!(
+            parse("a + 1").unwrap().eval(&env::Env::default()),
+            Err(Error::Runtime(
+                RuntimeError::from(RuntimeErrorKind::UndefinedBinding("a".to_string())).with_span(
+                    Span {
+                        start: 0,
+                        end: 5,
+                        line: 1,
+                        column: 1,
+                    }
+                )
+            )),
+        );
+    }
+
+    #[test]
+    fn report_break_outside_loop() {
+        assert_eq!(
+            parse("{ break }").unwrap().eval(&env::Env::default()),
+            Err(Error::Runtime(
+                RuntimeError::from(RuntimeErrorKind::BreakOutsideLoop).with_span(Span {
+                    start: 0,
+                    end: 9,
+                    line: 1,
+                    column: 1,
+                })
+            )),
+        );
     }
 }
@@ -0,0 +1,414 @@
+use std::rc::Rc;
+
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::{ParseFailure, RuntimeError, RuntimeErrorKind};
+use crate::expression::Expression;
+use crate::map;
+use crate::utils;
+use crate::value::Value;
+
+/// The contents of a list value. Lists are never changed once made: copies
+/// share their items, and `push` returns a new list, so a list can be neither
+/// changed behind a binding's back nor made to contain itself.
+pub type List = Rc<Vec<Value>>;
+
+#[derive(Debug, PartialEq)]
+pub struct ListLiteral {
+    pub items: Vec<Expression>,
+}
+
+impl ListLiteral {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let (s, items) = utils::extract_list("[", "]", Expression::new, s)?;
+        Ok((s, Self { items }))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let items = self
+            .items
+            .iter()
+            .map(|item| item.eval(env))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Value::list(items))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Subscript {
+    Index(Box<Expression>),
+    /// `start..end` or `start..=end`, where either bound may be left out.
+    Slice {
+        start: Option<Box<Expression>>,
+        end: Option<Box<Expression>>,
+        inclusive: bool,
+    },
+}
+
+/// Indexing into a list with `xs[i]`, taking a slice with `xs[i..j]` or
+/// looking up a key with `m["key"]`.
+#[derive(Debug, PartialEq)]
+pub struct Index {
+    pub target: Box<Expression>,
+    pub subscript: Subscript,
+}
+
+impl Index {
+    /// Parses the bracketed subscript that follows `target`.
+    pub fn new(target: Expression, s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_tag("[", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        // The subscript is parsed without ranges, so that the slice syntax
+        // can leave out either bound.
+        let (s, start) = if s.starts_with("..") {
+            (s, None)
+        } else {
+            let (s, start) = Expression::new_without_range(s)?;
+            (s, Some(Box::new(start)))
+        };
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let dots = utils::extract_tag("..=", s)
+            .map(|s| (s, true))
+            .or_else(|_| utils::extract_tag("..", s).map(|s| (s, false)));
+
+        let (s, subscript) = match (start, dots) {
+            (start, Ok((after_dots, inclusive))) => {
+                let (after_dots, _) = utils::extract_whitespaces(after_dots);
+                let (s, end) = match Expression::new_without_range(after_dots) {
+                    Ok((s, end)) => (s, Some(Box::new(end))),
+                    Err(_) => (after_dots, None),
+                };
+                (
+                    s,
+                    Subscript::Slice {
+                        start,
+                        end,
+                        inclusive,
+                    },
+                )
+            }
+            (Some(index), Err(_)) => (s, Subscript::Index(index)),
+            (None, Err(_)) => unreachable!("a subscript without a start begins with `..`"),
+        };
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let s = utils::extract_tag("]", s)?;
+
+        Ok((
+            s,
+            Self {
+                target: Box::new(target),
+                subscript,
+            },
+        ))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        match self.target.eval(env)? {
+            Value::List(list) => self.eval_on_list(&list, env),
+            Value::Map(map) => match &self.subscript {
+                Subscript::Index(key) => {
+                    let key = map::eval_key(key, env)?;
+                    Ok(map::lookup(&map, &key)?)
+                }
+                Subscript::Slice { .. } => Err(RuntimeErrorKind::TypeError(format!(
+                    "cannot slice {}, it is not a list",
+                    Value::Map(map)
+                ))
+                .into()),
+            },
+            target => Err(RuntimeErrorKind::TypeError(format!(
+                "cannot index into {}, it is not a list or map",
+                target
+            ))
+            .into()),
+        }
+    }
+
+    fn eval_on_list(&self, list: &List, env: &Env) -> Result<Value, ControlFlow> {
+        match &self.subscript {
+            Subscript::Index(index) => match index.eval(env)? {
+                Value::Number(index) => usize::try_from(index)
+                    .ok()
+                    .and_then(|idx| list.get(idx))
+                    .cloned()
+                    .ok_or_else(|| {
+                        RuntimeError::from(RuntimeErrorKind::IndexOutOfBounds {
+                            index,
+                            len: list.len(),
+                        })
+                        .into()
+                    }),
+                Value::Range(range) => Ok(slice(list, range.start, Some(range.end_exclusive()))?),
+                index => Err(RuntimeError::from(RuntimeErrorKind::TypeError(format!(
+                    "list index must be a number or range, found {}",
+                    index
+                )))
+                .into()),
+            },
+            Subscript::Slice {
+                start,
+                end,
+                inclusive,
+            } => {
+                let start = match start {
+                    Some(start) => eval_bound(start, env)?,
+                    None => 0,
+                };
+                let end = match end {
+                    Some(end) if *inclusive => Some(eval_bound(end, env)?.saturating_add(1)),
+                    Some(end) => Some(eval_bound(end, env)?),
+                    None => None,
+                };
+
+                Ok(slice(list, start, end)?)
+            }
+        }
+    }
+}
+
+/// Copies the items from `start` up to but excluding `end`, which defaults to
+/// the end of the list.
+fn slice(list: &List, start: i64, end: Option<i64>) -> Result<Value, RuntimeError> {
+    let end = end.unwrap_or(list.len() as i64);
+
+    match (usize::try_from(start), usize::try_from(end)) {
+        (Ok(from), Ok(to)) if from <= to && to <= list.len() => {
+            Ok(Value::list(list[from..to].to_vec()))
+        }
+        _ => Err(RuntimeErrorKind::SliceOutOfBounds {
+            start,
+            end,
+            len: list.len(),
+        }
+        .into()),
+    }
+}
+
+fn eval_bound(bound: &Expression, env: &Env) -> Result<i64, ControlFlow> {
+    match bound.eval(env)? {
+        Value::Number(n) => Ok(n),
+        bound => Err(RuntimeError::from(RuntimeErrorKind::TypeError(format!(
+            "slice bound must be a number, found {}",
+            bound
+        )))
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding_usage::BindingUsage;
+    use crate::block::Block;
+    use crate::expression::Number;
+
+    fn eval_str(s: &str) -> Result<Value, ControlFlow> {
+        let (_, block) = Block::new(s).unwrap();
+        block.eval(&Env::default())
+    }
+
+    fn numbers(ns: &[i64]) -> Value {
+        Value::list(ns.iter().copied().map(Value::Number).collect())
+    }
+
+    #[test]
+    fn parse_list_literal() {
+        assert_eq!(
+            ListLiteral::new("[1, [], x]"),
+            Ok((
+                "",
+                ListLiteral {
+                    items: vec![
+                        Expression::Number(Number(1)),
+                        Expression::List(ListLiteral { items: Vec::new() }),
+                        Expression::BindingUsage(BindingUsage {
+                            name: "x".to_string(),
+                        }),
+                    ],
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_index() {
+        assert_eq!(
+            Expression::new("xs[0]"),
+            Ok((
+                "",
+                Expression::Index(Index {
+                    target: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "xs".to_string(),
+                    })),
+                    subscript: Subscript::Index(Box::new(Expression::Number(Number(0)))),
+                }),
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_open_slice() {
+        assert_eq!(
+            Expression::new("xs[..2]"),
+            Ok((
+                "",
+                Expression::Index(Index {
+                    target: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "xs".to_string(),
+                    })),
+                    subscript: Subscript::Slice {
+                        start: None,
+                        end: Some(Box::new(Expression::Number(Number(2)))),
+                        inclusive: false,
+                    },
+                }),
+            )),
+        );
+    }
+
+    #[test]
+    fn do_not_parse_index_across_whitespace() {
+        assert_eq!(Expression::new("xs\n[0]").map(|(s, _)| s), Ok("\n[0]"));
+    }
+
+    #[test]
+    fn eval_list_of_any_values() {
+        assert_eq!(
+            eval_str("{ fn f() { 1 }; [f, [true], \"a\"][0]() }"),
+            Ok(Value::Number(1))
+        );
+    }
+
+    #[test]
+    fn eval_nested_index() {
+        assert_eq!(
+            eval_str("{ let xs = [[1, 2], [3, 4]]\n xs[1][0] }"),
+            Ok(Value::Number(3))
+        );
+    }
+
+    #[test]
+    fn eval_index_out_of_bounds() {
+        assert_eq!(
+            eval_str("{ [1, 2, 3][3] }"),
+            Err(RuntimeErrorKind::IndexOutOfBounds { index: 3, len: 3 }.into()),
+        );
+        assert_eq!(
+            eval_str("{ [1, 2, 3][-1] }"),
+            Err(RuntimeErrorKind::IndexOutOfBounds { index: -1, len: 3 }.into()),
+        );
+    }
+
+    #[test]
+    fn eval_index_with_non_number() {
+        assert_eq!(
+            eval_str("{ [1][true] }"),
+            Err(RuntimeErrorKind::TypeError(
+                "list index must be a number or range, found true".to_string()
+            )
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_index_into_non_list() {
+        assert_eq!(
+            eval_str("{ 1[0] }"),
+            Err(RuntimeErrorKind::TypeError(
+                "cannot index into 1, it is not a list or map".to_string()
+            )
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_slices() {
+        assert_eq!(eval_str("{ [1, 2, 3, 4][1..3] }"), Ok(numbers(&[2, 3])));
+        assert_eq!(eval_str("{ [1, 2, 3, 4][2..] }"), Ok(numbers(&[3, 4])));
+        assert_eq!(eval_str("{ [1, 2, 3, 4][..] }"), Ok(numbers(&[1, 2, 3, 4])));
+        assert_eq!(eval_str("{ [1, 2][1..1] }"), Ok(numbers(&[])));
+    }
+
+    #[test]
+    fn eval_slice_out_of_bounds() {
+        assert_eq!(
+            eval_str("{ [1, 2, 3][2..5] }"),
+            Err(RuntimeErrorKind::SliceOutOfBounds {
+                start: 2,
+                end: 5,
+                len: 3,
+            }
+            .into()),
+        );
+        assert_eq!(
+            eval_str("{ [1, 2, 3][2..1] }"),
+            Err(RuntimeErrorKind::SliceOutOfBounds {
+                start: 2,
+                end: 1,
+                len: 3,
+            }
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_concatenation_makes_new_list() {
+        assert_eq!(
+            eval_str("{ let mut xs = [1]\n let ys = xs + [2, 3]\n xs = push(xs, 9)\n [xs, ys] }"),
+            Ok(Value::list(vec![numbers(&[1, 9]), numbers(&[1, 2, 3])])),
+        );
+    }
+
+    #[test]
+    fn eval_len_and_push() {
+        assert_eq!(
+            eval_str("{ let mut xs = []\n xs = push(xs, 1)\n xs = push(xs, [2])\n len(xs) }"),
+            Ok(Value::Number(2))
+        );
+    }
+
+    #[test]
+    fn eval_list_equality() {
+        assert_eq!(
+            eval_str("{ [1, [2]] == [1, [2]] && [1] != [2] }"),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn eval_index_into_list_replaced_by_subscript() {
+        assert_eq!(
+            eval_str("{ let mut xs = [1]\n xs[{ xs = push(xs, 2)\n 1 }] }"),
+            Err(RuntimeErrorKind::IndexOutOfBounds { index: 1, len: 1 }.into()),
+        );
+    }
+
+    #[test]
+    fn eval_push_leaves_list_unchanged() {
+        assert_eq!(
+            eval_str("{ const xs = [1]\n let ys = push(xs, 2)\n [xs, ys] }"),
+            Ok(Value::list(vec![numbers(&[1]), numbers(&[1, 2])])),
+        );
+    }
+
+    #[test]
+    fn eval_push_of_list_onto_itself() {
+        assert_eq!(
+            eval_str("{ let mut xs = [1]\n xs = push(xs, xs)\n [xs == xs, xs] }"),
+            Ok(Value::list(vec![
+                Value::Bool(true),
+                Value::list(vec![Value::Number(1), numbers(&[1])]),
+            ])),
+        );
+        assert_eq!(
+            eval_str("{ let mut xs = [1]\n xs = push(xs, xs)\n xs }")
+                .unwrap()
+                .to_string(),
+            "[1, [1]]",
+        );
+    }
+}
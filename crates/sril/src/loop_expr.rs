@@ -0,0 +1,513 @@
+use std::rc::Rc;
+
+use crate::binding_def::BindingKind;
+use crate::block::Block;
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::{ParseFailure, RuntimeErrorKind};
+use crate::expression::Expression;
+use crate::utils;
+use crate::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub struct While {
+    pub condition: Box<Expression>,
+    pub body: Block,
+}
+
+impl While {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_keyword("while", s)?;
+        let (s, _) = utils::extract_required_whitespaces(s)?;
+
+        let (s, condition) = Expression::new(s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, body) = Block::new(s)?;
+
+        Ok((
+            s,
+            Self {
+                condition: Box::new(condition),
+                body,
+            },
+        ))
+    }
+
+    /// Like `loop`, evaluates to the value carried by a `break`, and to `()`
+    /// once the condition turns false.
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        loop {
+            match self.condition.eval(env)? {
+                Value::Bool(true) => {}
+                Value::Bool(false) => return Ok(Value::Unit),
+                condition => {
+                    return Err(RuntimeErrorKind::TypeError(format!(
+                        "while condition must be a boolean, found {}",
+                        condition
+                    ))
+                    .into())
+                }
+            }
+
+            match self.body.eval(env) {
+                Ok(_) | Err(ControlFlow::Continue) => {}
+                Err(ControlFlow::Break(value)) => return Ok(value),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Loop {
+    pub body: Block,
+}
+
+impl Loop {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_keyword("loop", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, body) = Block::new(s)?;
+
+        Ok((s, Self { body }))
+    }
+
+    /// Evaluates to the value carried by the `break` that ends the loop.
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        loop {
+            match self.body.eval(env) {
+                Ok(_) | Err(ControlFlow::Continue) => {}
+                Err(ControlFlow::Break(value)) => return Ok(value),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct For {
+    pub variable: String,
+    pub iterable: Box<Expression>,
+    pub body: Block,
+}
+
+impl For {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_keyword("for", s)?;
+        let (s, _) = utils::extract_required_whitespaces(s)?;
+
+        let (s, variable) = utils::extract_identifier(s)?;
+        let (s, _) = utils::extract_required_whitespaces(s)?;
+
+        let s = utils::extract_keyword("in", s)?;
+        let (s, _) = utils::extract_required_whitespaces(s)?;
+
+        let (s, iterable) = Expression::new(s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, body) = Block::new(s)?;
+
+        Ok((
+            s,
+            Self {
+                variable: variable.to_string(),
+                iterable: Box::new(iterable),
+                body,
+            },
+        ))
+    }
+
+    /// Runs the body once per item of a range, list, map (its keys) or
+    /// string (its characters), each time in a new scope holding the loop
+    /// variable. Evaluates to the value of a `break`, or `()` if it runs out
+    /// of items.
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let items: Box<dyn Iterator<Item = Value>> = match self.iterable.eval(env)? {
+            Value::Range(range) => Box::new(range.iter().map(Value::Number)),
+            Value::List(list) => Box::new(Rc::unwrap_or_clone(list).into_iter()),
+            Value::Map(map) => Box::new(
+                map.keys()
+                    .cloned()
+                    .map(Value::String)
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            Value::String(s) => Box::new(
+                s.chars()
+                    .map(|c| Value::String(c.to_string()))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+            iterable => {
+                return Err(RuntimeErrorKind::TypeError(format!(
+                    "cannot iterate over {}",
+                    iterable
+                ))
+                .into())
+            }
+        };
+
+        for item in items {
+            let iteration_env = env.create_child();
+            iteration_env.store_binding(self.variable.clone(), item, BindingKind::Let)?;
+
+            match self.body.eval(&iteration_env) {
+                Ok(_) | Err(ControlFlow::Continue) => {}
+                Err(ControlFlow::Break(value)) => return Ok(value),
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Value::Unit)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Break {
+    pub value: Option<Box<Expression>>,
+}
+
+impl Break {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_keyword("break", s)?;
+
+        // The value has to start on the same line, otherwise the next
+        // statement would be swallowed as the value of a bare `break`.
+        let (after_spaces, _) = utils::extract_inline_whitespaces(s);
+
+        match Expression::new(after_spaces) {
+            Ok((s, value)) => Ok((
+                s,
+                Self {
+                    value: Some(Box::new(value)),
+                },
+            )),
+            Err(_) => Ok((s, Self { value: None })),
+        }
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let value = match &self.value {
+            Some(value) => value.eval(env)?,
+            None => Value::Unit,
+        };
+
+        Err(ControlFlow::Break(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding_usage::BindingUsage;
+    use crate::expression::{Number, Operator};
+    use crate::statement::Statement;
+
+    #[test]
+    fn parse_while() {
+        assert_eq!(
+            While::new("while x < 3 { x }"),
+            Ok((
+                "",
+                While {
+                    condition: Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                            name: "x".to_string(),
+                        })),
+                        rhs: Box::new(Expression::Number(Number(3))),
+                        op: Operator::Lt,
+                    }),
+                    body: Block {
+                        statements: vec![Statement::Expression(Expression::BindingUsage(
+                            BindingUsage {
+                                name: "x".to_string(),
+                            },
+                        ))],
+                        locations: Vec::new(),
+                    },
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_loop() {
+        assert_eq!(
+            Loop::new("loop { continue }"),
+            Ok((
+                "",
+                Loop {
+                    body: Block {
+                        statements: vec![Statement::Expression(Expression::Continue)],
+                        locations: Vec::new(),
+                    },
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_break_with_value() {
+        assert_eq!(
+            Break::new("break 1 + 1"),
+            Ok((
+                "",
+                Break {
+                    value: Some(Box::new(Expression::Operation {
+                        lhs: Box::new(Expression::Number(Number(1))),
+                        rhs: Box::new(Expression::Number(Number(1))),
+                        op: Operator::Add,
+                    })),
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_break_without_value() {
+        assert_eq!(Break::new("break }"), Ok((" }", Break { value: None })));
+    }
+
+    #[test]
+    fn parse_break_does_not_take_value_from_next_line() {
+        assert_eq!(Break::new("break\nx"), Ok(("\nx", Break { value: None })));
+    }
+
+    #[test]
+    fn eval_while_that_never_runs() {
+        let (_, while_expr) = While::new("while false { 1 / 0 }").unwrap();
+        assert_eq!(while_expr.eval(&Env::default()), Ok(Value::Unit));
+    }
+
+    #[test]
+    fn eval_while_with_break() {
+        let (_, while_expr) = While::new("while true { break }").unwrap();
+        assert_eq!(while_expr.eval(&Env::default()), Ok(Value::Unit));
+    }
+
+    #[test]
+    fn eval_while_with_break_value() {
+        let (_, while_expr) = While::new("while true { break 5 }").unwrap();
+        assert_eq!(while_expr.eval(&Env::default()), Ok(Value::Number(5)));
+    }
+
+    #[test]
+    fn eval_while_with_non_bool_condition() {
+        let (_, while_expr) = While::new("while 1 { }").unwrap();
+        assert_eq!(
+            while_expr.eval(&Env::default()),
+            Err(RuntimeErrorKind::TypeError(
+                "while condition must be a boolean, found 1".to_string()
+            )
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_while_counting() {
+        let (_, block) = Block::new(
+            "{
+                let mut i = 0
+                let mut sum = 0
+                while i < 5 {
+                    i += 1
+                    if i == 2 { continue }
+                    sum += i
+                }
+                sum
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(block.eval(&Env::default()), Ok(Value::Number(13)));
+    }
+
+    #[test]
+    fn eval_loop_with_break_value() {
+        let (_, loop_expr) = Loop::new("loop { break 42 }").unwrap();
+        assert_eq!(loop_expr.eval(&Env::default()), Ok(Value::Number(42)));
+    }
+
+    #[test]
+    fn eval_break_in_nested_block() {
+        let (_, loop_expr) = Loop::new("loop { if true { { break 7 } }; 1 / 0 }").unwrap();
+        assert_eq!(loop_expr.eval(&Env::default()), Ok(Value::Number(7)));
+    }
+
+    #[test]
+    fn eval_break_only_leaves_innermost_loop() {
+        let (_, loop_expr) = Loop::new("loop { break loop { break 1 } + 1 }").unwrap();
+        assert_eq!(loop_expr.eval(&Env::default()), Ok(Value::Number(2)));
+    }
+
+    #[test]
+    fn eval_loop_propagates_errors() {
+        let (_, loop_expr) = Loop::new("loop { 1 / 0 }").unwrap();
+        assert_eq!(
+            loop_expr.eval(&Env::default()),
+            Err(RuntimeErrorKind::DivisionByZero.into()),
+        );
+    }
+
+    #[test]
+    fn parse_for() {
+        assert_eq!(
+            For::new("for x in xs { x }"),
+            Ok((
+                "",
+                For {
+                    variable: "x".to_string(),
+                    iterable: Box::new(Expression::BindingUsage(BindingUsage {
+                        name: "xs".to_string(),
+                    })),
+                    body: Block {
+                        statements: vec![Statement::Expression(Expression::BindingUsage(
+                            BindingUsage {
+                                name: "x".to_string(),
+                            },
+                        ))],
+                        locations: Vec::new(),
+                    },
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_for_over_range() {
+        assert_eq!(
+            For::new("for i in 0..=n {}").map(|(_, for_expr)| for_expr.iterable),
+            Ok(Box::new(Expression::Range {
+                start: Box::new(Expression::Number(Number(0))),
+                end: Box::new(Expression::BindingUsage(BindingUsage {
+                    name: "n".to_string(),
+                })),
+                inclusive: true,
+            })),
+        );
+    }
+
+    fn eval_block(s: &str) -> Result<Value, ControlFlow> {
+        let (_, block) = Block::new(s).unwrap();
+        block.eval(&Env::default())
+    }
+
+    #[test]
+    fn eval_for_over_ranges() {
+        assert_eq!(
+            eval_block(
+                "{
+                    let mut sum = 0
+                    for i in 0..10 { sum += i }
+                    for i in 1..=3 { sum += i * 100 }
+                    for i in 5..5 { sum = -1 }
+                    sum
+                }",
+            ),
+            Ok(Value::Number(645)),
+        );
+    }
+
+    #[test]
+    fn eval_for_over_stored_range() {
+        assert_eq!(
+            eval_block(
+                "{
+                    let r = 2..=4
+                    let mut product = 1
+                    for i in r { product *= i }
+                    product
+                }",
+            ),
+            Ok(Value::Number(24)),
+        );
+    }
+
+    #[test]
+    fn eval_for_with_break_value() {
+        assert_eq!(
+            eval_block("{ for i in 0..10 { if i == 3 { break i * 2 } } }"),
+            Ok(Value::Number(6)),
+        );
+    }
+
+    #[test]
+    fn eval_for_over_list_with_break_and_continue() {
+        assert_eq!(
+            eval_block(
+                "{
+                    let mut seen = \"\"
+                    for item in [\"a\", \"b\", \"c\", \"d\"] {
+                        if item == \"b\" { continue }
+                        if item == \"d\" { break }
+                        seen = seen + item
+                    }
+                    seen
+                }",
+            ),
+            Ok(Value::String("ac".to_string())),
+        );
+    }
+
+    #[test]
+    fn eval_for_over_map_keys_and_string() {
+        assert_eq!(
+            eval_block(
+                "{
+                    let mut out = []
+                    for key in { b: 1, a: 2 } { out = push(out, key) }
+                    for c in \"hé\" { out = push(out, c) }
+                    out
+                }",
+            ),
+            Ok(Value::list(
+                ["a", "b", "h", "é"]
+                    .iter()
+                    .map(|s| Value::String(s.to_string()))
+                    .collect()
+            )),
+        );
+    }
+
+    #[test]
+    fn eval_for_binds_fresh_variable_per_iteration() {
+        assert_eq!(
+            eval_block(
+                "{
+                    let mut closures = []
+                    for i in 0..3 { closures = push(closures, || i) }
+                    closures[0]() + closures[2]()
+                }",
+            ),
+            Ok(Value::Number(2)),
+        );
+    }
+
+    #[test]
+    fn eval_for_does_not_leak_variable() {
+        assert_eq!(
+            eval_block("{ for i in 0..1 {}\n i }"),
+            Err(RuntimeErrorKind::UndefinedBinding("i".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn eval_for_over_list_being_pushed_to() {
+        assert_eq!(
+            eval_block("{ let mut xs = [1, 2]\n for x in xs { xs = push(xs, x) }\n xs }"),
+            Ok(Value::list(
+                [1, 2, 1, 2].iter().copied().map(Value::Number).collect()
+            )),
+        );
+    }
+
+    #[test]
+    fn eval_for_over_non_iterable() {
+        assert_eq!(
+            eval_block("{ for x in 1 {} }"),
+            Err(RuntimeErrorKind::TypeError("cannot iterate over 1".to_string()).into()),
+        );
+    }
+}
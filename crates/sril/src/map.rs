@@ -0,0 +1,308 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::{ParseFailure, RuntimeError, RuntimeErrorKind};
+use crate::expression::Expression;
+use crate::string_literal::{StringLiteral, StringPart};
+use crate::utils;
+use crate::value::Value;
+
+/// The entries of a map value, kept in key order. Like lists, maps are never
+/// changed once made, and `insert` returns a new map.
+pub type Map = Rc<BTreeMap<String, Value>>;
+
+#[derive(Debug, PartialEq)]
+pub struct MapLiteral {
+    /// A bare identifier key is stored as the equivalent string literal.
+    pub entries: Vec<(StringLiteral, Expression)>,
+}
+
+impl MapLiteral {
+    /// Parses `{ "a": 1, b: 2 }`. Since an empty pair of braces is an empty
+    /// block, the empty map is written `{:}`.
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        if let Ok(after_map) = Self::new_empty(s) {
+            return Ok((
+                after_map,
+                Self {
+                    entries: Vec::new(),
+                },
+            ));
+        }
+
+        let (after_map, entries) = utils::extract_list("{", "}", Self::new_entry, s)?;
+
+        // `{}` has to stay an empty block.
+        if entries.is_empty() {
+            return Err(ParseFailure::new(s, "map entry"));
+        }
+
+        Ok((after_map, Self { entries }))
+    }
+
+    fn new_empty(s: &str) -> Result<&str, ParseFailure> {
+        let s = utils::extract_tag("{", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+        let s = utils::extract_tag(":", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+        utils::extract_tag("}", s)
+    }
+
+    fn new_entry(s: &str) -> Result<(&str, (StringLiteral, Expression)), ParseFailure> {
+        let (s, key) = StringLiteral::new(s).or_else(|err| {
+            utils::extract_identifier(s)
+                .map(|(s, name)| {
+                    let key = StringLiteral {
+                        parts: vec![StringPart::Text(name.to_string())],
+                    };
+                    (s, key)
+                })
+                .map_err(|other| err.or(other))
+        })?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let s = utils::extract_tag(":", s)?;
+        let (s, _) = utils::extract_whitespaces(s);
+
+        let (s, value) = Expression::new(s)?;
+
+        Ok((s, (key, value)))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        let mut entries = BTreeMap::new();
+
+        for (key, value) in &self.entries {
+            entries.insert(key.eval_to_string(env)?, value.eval(env)?);
+        }
+
+        Ok(Value::map(entries))
+    }
+}
+
+/// Field access such as `config.name`, which is short for `config["name"]`.
+#[derive(Debug, PartialEq)]
+pub struct FieldAccess {
+    pub target: Box<Expression>,
+    pub name: String,
+}
+
+impl FieldAccess {
+    /// Parses the `.name` that follows `target`.
+    pub fn new(target: Expression, s: &str) -> Result<(&str, Self), ParseFailure> {
+        let s = utils::extract_tag(".", s)?;
+        let (s, name) = utils::extract_identifier(s)?;
+
+        Ok((
+            s,
+            Self {
+                target: Box::new(target),
+                name: name.to_string(),
+            },
+        ))
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        match self.target.eval(env)? {
+            Value::Map(map) => Ok(lookup(&map, &self.name)?),
+            target => Err(RuntimeErrorKind::TypeError(format!(
+                "cannot access field ‘{}’ of {}, it is not a map",
+                self.name, target
+            ))
+            .into()),
+        }
+    }
+}
+
+pub(crate) fn eval_key(key: &Expression, env: &Env) -> Result<String, ControlFlow> {
+    match key.eval(env)? {
+        Value::String(key) => Ok(key),
+        key => Err(RuntimeError::from(RuntimeErrorKind::TypeError(format!(
+            "map key must be a string, found {}",
+            key
+        )))
+        .into()),
+    }
+}
+
+pub(crate) fn lookup(map: &Map, key: &str) -> Result<Value, RuntimeError> {
+    map.get(key)
+        .cloned()
+        .ok_or_else(|| RuntimeErrorKind::KeyNotFound(key.to_string()).into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::Block;
+    use crate::expression::Number;
+
+    fn eval_str(s: &str) -> Result<Value, ControlFlow> {
+        let (_, block) = Block::new(s).unwrap();
+        block.eval(&Env::default())
+    }
+
+    fn text(s: &str) -> StringLiteral {
+        StringLiteral {
+            parts: vec![StringPart::Text(s.to_string())],
+        }
+    }
+
+    #[test]
+    fn parse_map_literal() {
+        assert_eq!(
+            MapLiteral::new("{ \"a\": 1,\n  b: 2, }"),
+            Ok((
+                "",
+                MapLiteral {
+                    entries: vec![
+                        (text("a"), Expression::Number(Number(1))),
+                        (text("b"), Expression::Number(Number(2))),
+                    ],
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_empty_map() {
+        assert_eq!(
+            Expression::new("{ : }"),
+            Ok((
+                "",
+                Expression::Map(MapLiteral {
+                    entries: Vec::new()
+                })
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_braces_without_entries_as_block() {
+        assert!(matches!(
+            Expression::new("{}"),
+            Ok(("", Expression::Block(_)))
+        ));
+        assert!(matches!(
+            Expression::new("{ b }"),
+            Ok(("", Expression::Block(_)))
+        ));
+        assert!(matches!(
+            Expression::new("{ \"b\" }"),
+            Ok(("", Expression::Block(_)))
+        ));
+    }
+
+    #[test]
+    fn parse_field_access() {
+        assert_eq!(
+            Expression::new("{ a: 1 }.a"),
+            Ok((
+                "",
+                Expression::FieldAccess(FieldAccess {
+                    target: Box::new(Expression::Map(MapLiteral {
+                        entries: vec![(text("a"), Expression::Number(Number(1)))],
+                    })),
+                    name: "a".to_string(),
+                }),
+            )),
+        );
+    }
+
+    #[test]
+    fn eval_field_access_and_key_lookup() {
+        assert_eq!(
+            eval_str("{ let m = { \"a\": 1, b: { c: [2, 3] } }\n m[\"a\"] + m.b.c[1] }"),
+            Ok(Value::Number(4))
+        );
+    }
+
+    #[test]
+    fn eval_interpolated_key() {
+        assert_eq!(
+            eval_str("{ let n = 1\n { \"key{n}\": true }.key1 }"),
+            Ok(Value::Bool(true))
+        );
+    }
+
+    #[test]
+    fn eval_missing_key() {
+        assert_eq!(
+            eval_str("{ { a: 1 }.b }"),
+            Err(RuntimeErrorKind::KeyNotFound("b".to_string()).into()),
+        );
+        assert_eq!(
+            eval_str("{ { a: 1 }[\"b\"] }"),
+            Err(RuntimeErrorKind::KeyNotFound("b".to_string()).into()),
+        );
+    }
+
+    #[test]
+    fn eval_non_string_key() {
+        assert_eq!(
+            eval_str("{ { a: 1 }[0] }"),
+            Err(
+                RuntimeErrorKind::TypeError("map key must be a string, found 0".to_string()).into()
+            ),
+        );
+    }
+
+    #[test]
+    fn eval_field_access_on_non_map() {
+        assert_eq!(
+            eval_str("{ [1].a }"),
+            Err(RuntimeErrorKind::TypeError(
+                "cannot access field ‘a’ of [1], it is not a map".to_string()
+            )
+            .into()),
+        );
+    }
+
+    #[test]
+    fn eval_insert_leaves_map_unchanged() {
+        assert_eq!(
+            eval_str(
+                "{
+                    const m = { a: 1 }
+                    let n = insert(m, \"m\", m)
+                    [m, n]
+                }",
+            )
+            .unwrap()
+            .to_string(),
+            r#"[{"a": 1}, {"a": 1, "m": {"a": 1}}]"#,
+        );
+    }
+
+    #[test]
+    fn eval_insert_keys_and_values() {
+        assert_eq!(
+            eval_str(
+                "{
+                    let mut m = { b: 2 }
+                    m = insert(m, \"a\", 1)
+                    m = insert(m, \"b\", 3)
+                    [len(m), keys(m), values(m)]
+                }",
+            ),
+            Ok(Value::list(vec![
+                Value::Number(2),
+                Value::list(vec![
+                    Value::String("a".to_string()),
+                    Value::String("b".to_string()),
+                ]),
+                Value::list(vec![Value::Number(1), Value::Number(3)]),
+            ])),
+        );
+    }
+
+    #[test]
+    fn eval_map_equality() {
+        assert_eq!(
+            eval_str("{ { a: 1, b: 2 } == { b: 2, \"a\": 1 } }"),
+            Ok(Value::Bool(true))
+        );
+    }
+}
@@ -1,30 +1,56 @@
+use crate::assignment::Assignment;
 use crate::binding_def::BindingDef;
+use crate::control_flow::ControlFlow;
 use crate::env::Env;
+use crate::error::ParseFailure;
 use crate::expression::Expression;
+use crate::func_def::FuncDef;
 use crate::value::Value;
 
 #[derive(Debug, PartialEq)]
 pub enum Statement {
     BindingDef(BindingDef),
+    FuncDef(FuncDef),
+    Assignment(Assignment),
     Expression(Expression),
 }
 
 impl Statement {
-    pub fn new(s: &str) -> Result<(&str, Self), String> {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
         BindingDef::new(s)
             .map(|(s, binding_definition)| (s, Self::BindingDef(binding_definition)))
-            .or_else(|_| {
-                Expression::new(s).map(|(s, expression)| (s, Self::Expression(expression)))
+            .or_else(|err| {
+                FuncDef::new(s)
+                    .map(|(s, func_def)| (s, Self::FuncDef(func_def)))
+                    .map_err(|other| err.or(other))
+            })
+            .or_else(|err| {
+                Assignment::new(s)
+                    .map(|(s, assignment)| (s, Self::Assignment(assignment)))
+                    .map_err(|other| err.or(other))
+            })
+            .or_else(|err| {
+                Expression::new(s)
+                    .map(|(s, expression)| (s, Self::Expression(expression)))
+                    .map_err(|other| err.or(other))
             })
     }
 
-    pub(crate) fn eval(&self, env: &mut Env) -> Result<Value, String> {
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
         match self {
             Statement::BindingDef(binding_def) => {
                 binding_def.eval(env)?;
                 Ok(Value::Unit)
             }
-            Statement::Expression(expression) => expression.eval(&env),
+            Statement::FuncDef(func_def) => {
+                func_def.eval(env)?;
+                Ok(Value::Unit)
+            }
+            Statement::Assignment(assignment) => {
+                assignment.eval(env)?;
+                Ok(Value::Unit)
+            }
+            Statement::Expression(expression) => expression.eval(env),
         }
     }
 }
@@ -32,6 +58,7 @@ impl Statement {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::binding_def::BindingKind;
     use crate::expression::{Number, Operator};
 
     #[test]
@@ -41,6 +68,7 @@ mod test {
             Ok((
                 "",
                 Statement::BindingDef(BindingDef {
+                    kind: BindingKind::Let,
                     name: "a".to_string(),
                     val: Expression::Number(Number(10)),
                 }),
@@ -55,8 +83,8 @@ mod test {
             Ok((
                 "",
                 Statement::Expression(Expression::Operation {
-                    lhs: Number(1),
-                    rhs: Number(1),
+                    lhs: Box::new(Expression::Number(Number(1))),
+                    rhs: Box::new(Expression::Number(Number(1))),
                     op: Operator::Add,
                 }),
             )),
@@ -67,10 +95,11 @@ mod test {
     fn eval_binding_def() {
         assert_eq!(
             Statement::BindingDef(BindingDef {
+                kind: BindingKind::Let,
                 name: "whatever".to_string(),
                 val: Expression::Number(Number(-10)),
             })
-            .eval(&mut Env::default()),
+            .eval(&Env::default()),
             Ok(Value::Unit),
         );
     }
@@ -78,7 +107,7 @@ mod test {
     #[test]
     fn eval_expr() {
         assert_eq!(
-            Statement::Expression(Expression::Number(Number(5))).eval(&mut Env::default()),
+            Statement::Expression(Expression::Number(Number(5))).eval(&Env::default()),
             Ok(Value::Number(5)),
         );
     }
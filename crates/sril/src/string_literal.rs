@@ -0,0 +1,184 @@
+use crate::control_flow::ControlFlow;
+use crate::env::Env;
+use crate::error::ParseFailure;
+use crate::expression::Expression;
+use crate::utils;
+use crate::value::Value;
+
+#[derive(Debug, PartialEq)]
+pub enum StringPart {
+    Text(String),
+    /// An expression between braces, such as `{count + 1}`.
+    Interpolation(Expression),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct StringLiteral {
+    pub parts: Vec<StringPart>,
+}
+
+impl StringLiteral {
+    pub fn new(s: &str) -> Result<(&str, Self), ParseFailure> {
+        let mut s = utils::extract_tag("\"", s)?;
+        let mut parts = Vec::new();
+
+        loop {
+            let (after_text, text) = utils::extract_string_text(s)?;
+            if !text.is_empty() {
+                parts.push(StringPart::Text(text));
+            }
+
+            if let Ok(after_quote) = utils::extract_tag("\"", after_text) {
+                return Ok((after_quote, Self { parts }));
+            }
+
+            let after_open = utils::extract_tag("{", after_text)?;
+            let (after_open, _) = utils::extract_whitespaces(after_open);
+
+            let (after_expression, expression) = Expression::new(after_open)?;
+            let (after_expression, _) = utils::extract_whitespaces(after_expression);
+
+            s = utils::extract_tag("}", after_expression)?;
+            parts.push(StringPart::Interpolation(expression));
+        }
+    }
+
+    pub(crate) fn eval(&self, env: &Env) -> Result<Value, ControlFlow> {
+        self.eval_to_string(env).map(Value::String)
+    }
+
+    pub(crate) fn eval_to_string(&self, env: &Env) -> Result<String, ControlFlow> {
+        let mut string = String::new();
+
+        for part in &self.parts {
+            match part {
+                StringPart::Text(text) => string.push_str(text),
+                // Strings are spliced in as they are rather than quoted the
+                // way the REPL shows them.
+                StringPart::Interpolation(expression) => match expression.eval(env)? {
+                    Value::String(s) => string.push_str(&s),
+                    value => string.push_str(&value.to_string()),
+                },
+            }
+        }
+
+        Ok(string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binding_def::BindingKind;
+    use crate::binding_usage::BindingUsage;
+    use crate::error::RuntimeErrorKind;
+    use crate::expression::{Number, Operator};
+
+    #[test]
+    fn parse_plain_string() {
+        assert_eq!(
+            StringLiteral::new(r#""hello" + x"#),
+            Ok((
+                " + x",
+                StringLiteral {
+                    parts: vec![StringPart::Text("hello".to_string())],
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_empty_string() {
+        assert_eq!(
+            StringLiteral::new(r#""""#),
+            Ok(("", StringLiteral { parts: Vec::new() })),
+        );
+    }
+
+    #[test]
+    fn parse_interpolated_string() {
+        assert_eq!(
+            StringLiteral::new(r#""hi {name}, {count + 1}!""#),
+            Ok((
+                "",
+                StringLiteral {
+                    parts: vec![
+                        StringPart::Text("hi ".to_string()),
+                        StringPart::Interpolation(Expression::BindingUsage(BindingUsage {
+                            name: "name".to_string(),
+                        })),
+                        StringPart::Text(", ".to_string()),
+                        StringPart::Interpolation(Expression::Operation {
+                            lhs: Box::new(Expression::BindingUsage(BindingUsage {
+                                name: "count".to_string(),
+                            })),
+                            rhs: Box::new(Expression::Number(Number(1))),
+                            op: Operator::Add,
+                        }),
+                        StringPart::Text("!".to_string()),
+                    ],
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn parse_escaped_braces_as_text() {
+        assert_eq!(
+            StringLiteral::new(r#""\{x\}""#),
+            Ok((
+                "",
+                StringLiteral {
+                    parts: vec![StringPart::Text("{x}".to_string())],
+                },
+            )),
+        );
+    }
+
+    #[test]
+    fn cannot_parse_unclosed_interpolation() {
+        assert_eq!(
+            StringLiteral::new(r#""a {b c}""#),
+            Err(ParseFailure::new(r#"c}""#, "`}`")),
+        );
+    }
+
+    #[test]
+    fn eval_interpolated_string() {
+        let env = Env::default();
+        env.store_binding(
+            "name".to_string(),
+            Value::String("Ada".to_string()),
+            BindingKind::Let,
+        )
+        .unwrap();
+        env.store_binding("count".to_string(), Value::Number(2), BindingKind::Let)
+            .unwrap();
+
+        let (_, literal) =
+            StringLiteral::new(r#""hello {name}, you have {count + 1} items""#).unwrap();
+
+        assert_eq!(
+            literal.eval(&env),
+            Ok(Value::String("hello Ada, you have 3 items".to_string())),
+        );
+    }
+
+    #[test]
+    fn eval_nested_interpolated_string() {
+        let (_, literal) = StringLiteral::new(r#""<{ "{1 < 2}" + "!" }>""#).unwrap();
+        assert_eq!(
+            literal.eval(&Env::default()),
+            Ok(Value::String("<true!>".to_string())),
+        );
+    }
+
+    #[test]
+    fn eval_interpolation_error() {
+        let (_, literal) = StringLiteral::new(r#""{missing}""#).unwrap();
+        assert_eq!(
+            literal.eval(&Env::default()),
+            Err(RuntimeErrorKind::UndefinedBinding("missing".to_string()).into()),
+        );
+    }
+}
@@ -1,8 +1,25 @@
+use std::cell::Cell;
+
+use crate::error::ParseFailure;
+
+/// The failure for input that does not start with what was `expected`.
+///
+/// The whitespace extractors leave an unterminated block comment in place, so
+/// when one shows up where a token was expected it is the comment that gets
+/// reported, at the end of the input where its `*/` is missing.
+pub(crate) fn expected(s: &str, expected: impl Into<String>) -> ParseFailure {
+    if s.starts_with("/*") {
+        ParseFailure::new("", "`*/` to end the comment")
+    } else {
+        ParseFailure::new(s, expected)
+    }
+}
+
 pub(crate) fn safe_extract(accept: impl Fn(char) -> bool, s: &str) -> (&str, &str) {
     let extracted_end = s
         .char_indices()
         .find_map(|(idx, c)| if accept(c) { None } else { Some(idx) })
-        .unwrap_or_else(|| s.len());
+        .unwrap_or(s.len());
 
     let extracted = &s[..extracted_end];
     let remainder = &s[extracted_end..];
@@ -10,57 +27,349 @@ pub(crate) fn safe_extract(accept: impl Fn(char) -> bool, s: &str) -> (&str, &st
     (remainder, extracted)
 }
 
-pub(crate) fn extract(
+pub(crate) fn extract<'a>(
     accept: impl Fn(char) -> bool,
-    s: &str,
-    error_message: String,
-) -> Result<(&str, &str), String> {
+    s: &'a str,
+    expected: &str,
+) -> Result<(&'a str, &'a str), ParseFailure> {
     let (remainder, extracted) = safe_extract(accept, s);
 
     if extracted.is_empty() {
-        Err(error_message)
+        Err(self::expected(s, expected))
     } else {
         Ok((remainder, extracted))
     }
 }
 
-pub(crate) fn extract_digits(s: &str) -> Result<(&str, &str), String> {
-    extract(|c| c.is_ascii_digit(), s, "Expected digits".to_string())
+pub(crate) fn extract_digits(s: &str) -> Result<(&str, &str), ParseFailure> {
+    extract(|c| c.is_ascii_digit(), s, "digits")
 }
 
-const WHITESPACE: &[char] = &[' ', '\n', '\t'];
+/// Extracts a float literal: an optionally negative integer part followed by
+/// a fractional part, an exponent or both, as in `3.14`, `1e-3` or `2.5E+10`.
+/// At least one digit has to follow the dot, so `1..3` is not a float.
+pub(crate) fn extract_float(s: &str) -> Result<(&str, &str), ParseFailure> {
+    let after_sign = s.strip_prefix('-').unwrap_or(s);
+    let (after_int, _) = extract_digits(after_sign)?;
+
+    let after_fraction = after_int
+        .strip_prefix('.')
+        .and_then(|s| extract_digits(s).ok())
+        .map(|(s, _)| s);
+
+    let after_exponent = after_fraction
+        .unwrap_or(after_int)
+        .strip_prefix(['e', 'E'])
+        .map(|s| s.strip_prefix(['-', '+']).unwrap_or(s))
+        .and_then(|s| extract_digits(s).ok())
+        .map(|(s, _)| s);
 
+    match after_exponent.or(after_fraction) {
+        Some(remainder) => Ok((remainder, &s[..s.len() - remainder.len()])),
+        None => Err(ParseFailure::new(after_int, "fractional part or exponent")),
+    }
+}
+
+const WHITESPACE: &[char] = &[' ', '\n', '\t', '\r'];
+
+/// Extracts whitespace along with any `// line` and `/* block */` comments.
 pub(crate) fn extract_whitespaces(s: &str) -> (&str, &str) {
-    safe_extract(|c| WHITESPACE.contains(&c), s)
+    let mut remainder = s;
+
+    loop {
+        let (after_whitespace, _) = safe_extract(|c| WHITESPACE.contains(&c), remainder);
+
+        match skip_comment(after_whitespace) {
+            Some(after_comment) => remainder = after_comment,
+            None => {
+                remainder = after_whitespace;
+                break;
+            }
+        }
+    }
+
+    (remainder, &s[..s.len() - remainder.len()])
+}
+
+/// Skips a comment at the start of `s`. Block comments nest, and one that is
+/// never closed is not skipped at all.
+fn skip_comment(s: &str) -> Option<&str> {
+    if let Some(comment) = s.strip_prefix("//") {
+        let line_end = comment.find('\n').unwrap_or(comment.len());
+        return Some(&comment[line_end..]);
+    }
+
+    let mut s = s.strip_prefix("/*")?;
+    let mut depth = 1;
+
+    while depth > 0 {
+        if let Some(after_open) = s.strip_prefix("/*") {
+            depth += 1;
+            s = after_open;
+        } else if let Some(after_close) = s.strip_prefix("*/") {
+            depth -= 1;
+            s = after_close;
+        } else {
+            let mut chars = s.chars();
+            chars.next()?;
+            s = chars.as_str();
+        }
+    }
+
+    Some(s)
 }
 
-pub(crate) fn extract_required_whitespaces(s: &str) -> Result<(&str, &str), String> {
-    extract(|c| WHITESPACE.contains(&c), s, "Expected space".to_string())
+/// Whitespace that does not cross a line break.
+pub(crate) fn extract_inline_whitespaces(s: &str) -> (&str, &str) {
+    safe_extract(|c| c == ' ' || c == '\t', s)
+}
+
+/// Like [`extract_whitespaces`], but a comment on its own is enough to
+/// separate two words.
+pub(crate) fn extract_required_whitespaces(s: &str) -> Result<(&str, &str), ParseFailure> {
+    match extract_whitespaces(s) {
+        (_, "") => Err(expected(s, "whitespace")),
+        extracted => Ok(extracted),
+    }
+}
+
+/// Extracts what has to come between two statements: a line break or a `;`,
+/// along with any whitespace and comments around them. Several in a row are
+/// fine.
+pub(crate) fn extract_statement_separator(s: &str) -> Result<(&str, &str), ParseFailure> {
+    let (mut remainder, whitespace) = extract_whitespaces(s);
+    let mut separated = whitespace.contains('\n');
+
+    while let Some(after_semicolon) = remainder.strip_prefix(';') {
+        separated = true;
+        (remainder, _) = extract_whitespaces(after_semicolon);
+    }
+
+    if separated {
+        Ok((remainder, &s[..s.len() - remainder.len()]))
+    } else {
+        Err(expected(remainder, "newline or `;`"))
+    }
 }
 
-pub(crate) fn extract_identifier(s: &str) -> Result<(&str, &str), String> {
+const KEYWORDS: &[&str] = &[
+    "let", "mut", "const", "true", "false", "if", "else", "while", "loop", "break", "continue",
+    "fn", "return", "for", "in",
+];
+
+pub(crate) fn extract_identifier(s: &str) -> Result<(&str, &str), ParseFailure> {
     let input_starts_with_alphabetic = s
         .chars()
         .next()
         .map(|c| c.is_ascii_alphabetic())
         .unwrap_or(false);
 
-    if input_starts_with_alphabetic {
-        extract(
-            |c| c.is_alphanumeric(),
-            s,
-            "Expected identifier".to_string(),
-        )
+    if !input_starts_with_alphabetic {
+        return Err(expected(s, "identifier"));
+    }
+
+    let (remainder, identifier) = extract(|c| c.is_alphanumeric(), s, "identifier")?;
+
+    if KEYWORDS.contains(&identifier) {
+        Err(expected(s, "identifier"))
     } else {
-        Err("Identifier not found".to_string())
+        Ok((remainder, identifier))
+    }
+}
+
+pub(crate) fn extract_tag<'a>(starting_text: &str, s: &'a str) -> Result<&'a str, ParseFailure> {
+    s.strip_prefix(starting_text)
+        .ok_or_else(|| expected(s, format!("`{}`", starting_text)))
+}
+
+/// Extracts a delimited, comma-separated list such as `(a, b, c)`, allowing
+/// whitespace around the items and a trailing comma.
+pub(crate) fn extract_list<'a, T>(
+    open: &str,
+    close: &str,
+    item: impl Fn(&'a str) -> Result<(&'a str, T), ParseFailure>,
+    s: &'a str,
+) -> Result<(&'a str, Vec<T>), ParseFailure> {
+    let mut s = extract_tag(open, s)?;
+    let mut items = Vec::new();
+
+    loop {
+        let (after_whitespace, _) = extract_whitespaces(s);
+
+        if let Ok(after_close) = extract_tag(close, after_whitespace) {
+            return Ok((after_close, items));
+        }
+
+        let (after_item, new_item) = item(after_whitespace).map_err(|err| {
+            if items.is_empty() {
+                err.or(expected(after_whitespace, format!("`{}`", close)))
+            } else {
+                err
+            }
+        })?;
+        items.push(new_item);
+
+        let (after_whitespace, _) = extract_whitespaces(after_item);
+        s = match extract_tag(",", after_whitespace) {
+            Ok(after_comma) => after_comma,
+            Err(err) => {
+                let after_close =
+                    extract_tag(close, after_whitespace).map_err(|other| err.or(other))?;
+                return Ok((after_close, items));
+            }
+        };
+    }
+}
+
+/// Extracts the parameter names of a function or closure, listed between
+/// `open` and `close`. A name may only be used once.
+pub(crate) fn extract_params<'a>(
+    open: &str,
+    close: &str,
+    s: &'a str,
+) -> Result<(&'a str, Vec<String>), ParseFailure> {
+    let (s, params) = extract_list(
+        open,
+        close,
+        |s| extract_identifier(s).map(|(remainder, name)| (remainder, (s, name))),
+        s,
+    )?;
+
+    let mut names: Vec<String> = Vec::with_capacity(params.len());
+    for (at_name, name) in params {
+        if names.iter().any(|other| other == name) {
+            return Err(ParseFailure::new(
+                at_name,
+                format!("a parameter name other than `{}`", name),
+            ));
+        }
+        names.push(name.to_string());
     }
+
+    Ok((s, names))
 }
 
-pub(crate) fn extract_tag<'a, 'b>(starting_text: &'a str, s: &'b str) -> Result<&'b str, String> {
-    if s.starts_with(starting_text) {
-        Ok(&s[starting_text.len()..])
+/// Like [`extract_tag`], but only matches whole words so that a keyword is
+/// not mistaken for the start of a longer identifier.
+pub(crate) fn extract_keyword<'a>(keyword: &str, s: &'a str) -> Result<&'a str, ParseFailure> {
+    let remainder = extract_tag(keyword, s)?;
+
+    if remainder.starts_with(|c: char| c.is_alphanumeric()) {
+        Err(expected(s, format!("`{}`", keyword)))
     } else {
-        Err(format!("expected {}", starting_text))
+        Ok(remainder)
+    }
+}
+
+/// Extracts the text of a string literal up to its closing quote or the
+/// next interpolated segment, whichever comes first, with the escape
+/// sequences resolved. The quote or brace itself is left in the remainder.
+pub(crate) fn extract_string_text(mut s: &str) -> Result<(&str, String), ParseFailure> {
+    let mut text = String::new();
+
+    loop {
+        let (after_chars, chars) = safe_extract(|c| !matches!(c, '"' | '{' | '\\'), s);
+        text.push_str(chars);
+
+        if after_chars.starts_with(['"', '{']) {
+            return Ok((after_chars, text));
+        }
+
+        let (after_escape, c) = extract_escape(after_chars)?;
+        text.push(c);
+        s = after_escape;
+    }
+}
+
+/// Reported where the closing quote is missing, which is as far as any other
+/// alternative could have got.
+fn unterminated_string() -> ParseFailure {
+    ParseFailure::new("", "`\"` to end the string")
+}
+
+fn extract_escape(s: &str) -> Result<(&str, char), ParseFailure> {
+    let after_backslash = s.strip_prefix('\\').ok_or_else(unterminated_string)?;
+
+    let c = match after_backslash.chars().next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('"') => '"',
+        Some('\\') => '\\',
+        Some('{') => '{',
+        Some('}') => '}',
+        Some('u') => return extract_unicode_escape(s),
+        Some(_) => return Err(ParseFailure::new(s, "escape sequence")),
+        None => return Err(unterminated_string()),
+    };
+
+    Ok((&after_backslash[1..], c))
+}
+
+/// Extracts a `\u{…}` escape of one to six hex digits naming a Unicode scalar
+/// value.
+fn extract_unicode_escape(s: &str) -> Result<(&str, char), ParseFailure> {
+    let invalid = || ParseFailure::new(s, "unicode escape such as `\\u{1F600}`");
+
+    let after_open = extract_tag("\\u{", s).map_err(|_| invalid())?;
+    let (after_digits, digits) = safe_extract(|c| c.is_ascii_hexdigit(), after_open);
+    let after_close = extract_tag("}", after_digits).map_err(|_| invalid())?;
+
+    if digits.is_empty() || digits.len() > 6 {
+        return Err(invalid());
+    }
+
+    u32::from_str_radix(digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .map(|c| (after_close, c))
+        .ok_or_else(invalid)
+}
+
+/// Number of expressions and blocks that may be nested inside each other in
+/// the source. Code written by hand stays far below this; the limit keeps the
+/// parser, and everything walking the tree it builds, from running out of
+/// stack on generated or hostile input.
+pub(crate) const MAX_NESTING_DEPTH: usize = 256;
+
+/// Stack that has to be left when going one level deeper into the source or
+/// into evaluation, and the size of the stack segment allocated otherwise.
+const STACK_RED_ZONE: usize = 128 * 1024;
+const STACK_SEGMENT_SIZE: usize = 2 * 1024 * 1024;
+
+thread_local! {
+    static NESTING_DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Runs `f` on a new stack segment if the current one is about to run out.
+pub(crate) fn with_stack<R>(f: impl FnOnce() -> R) -> R {
+    stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT_SIZE, f)
+}
+
+/// Parses something that may contain further expressions or blocks one level
+/// deeper into the source, failing at `s` once that is more than
+/// [`MAX_NESTING_DEPTH`] levels deep.
+pub(crate) fn extract_nested<'a, T>(
+    parse: impl FnOnce(&'a str) -> Result<(&'a str, T), ParseFailure>,
+    s: &'a str,
+) -> Result<(&'a str, T), ParseFailure> {
+    let depth = NESTING_DEPTH.get();
+    if depth >= MAX_NESTING_DEPTH {
+        return Err(ParseFailure::nested_too_deeply(s));
+    }
+
+    NESTING_DEPTH.set(depth + 1);
+    let _nesting = Nesting(depth);
+
+    with_stack(|| parse(s))
+}
+
+/// Leaves a level of nesting entered by [`extract_nested`] when dropped, so
+/// that the depth is restored however parsing ends.
+struct Nesting(usize);
+
+impl Drop for Nesting {
+    fn drop(&mut self) {
+        NESTING_DEPTH.set(self.0);
     }
 }
 
@@ -80,12 +389,15 @@ mod tests {
 
     #[test]
     fn do_not_extract_digits_when_input_is_invalid() {
-        assert_eq!(extract_digits("abcd"), Err("Expected digits".to_string()));
+        assert_eq!(
+            extract_digits("abcd"),
+            Err(ParseFailure::new("abcd", "digits")),
+        );
     }
 
     #[test]
     fn do_not_extract_anything_from_empty_input() {
-        assert_eq!(extract_digits(""), Err("Expected digits".to_string()));
+        assert_eq!(extract_digits(""), Err(ParseFailure::new("", "digits")));
     }
 
     #[test]
@@ -93,16 +405,124 @@ mod tests {
         assert_eq!(extract_digits("100"), Ok(("", "100")));
     }
 
+    #[test]
+    fn extract_float_with_fraction() {
+        assert_eq!(extract_float("-3.14+1"), Ok(("+1", "-3.14")));
+    }
+
+    #[test]
+    fn extract_float_with_exponent() {
+        assert_eq!(extract_float("1e-3 "), Ok((" ", "1e-3")));
+        assert_eq!(extract_float("2.5E+10"), Ok(("", "2.5E+10")));
+    }
+
+    #[test]
+    fn do_not_extract_integer_as_float() {
+        assert_eq!(
+            extract_float("12 "),
+            Err(ParseFailure::new(" ", "fractional part or exponent")),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_float_from_range() {
+        assert_eq!(
+            extract_float("1..3"),
+            Err(ParseFailure::new("..3", "fractional part or exponent")),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_incomplete_exponent_as_part_of_float() {
+        assert_eq!(extract_float("1.5e"), Ok(("e", "1.5")));
+    }
+
     #[test]
     fn extract_spaces() {
         assert_eq!(extract_whitespaces("    1"), ("1", "    "));
     }
 
+    #[test]
+    fn extract_windows_line_endings() {
+        assert_eq!(extract_whitespaces("\r\n\r\n1"), ("1", "\r\n\r\n"));
+    }
+
+    #[test]
+    fn extract_line_comments() {
+        assert_eq!(
+            extract_whitespaces(" // one\n// two\r\n  x // three"),
+            ("x // three", " // one\n// two\r\n  "),
+        );
+    }
+
+    #[test]
+    fn extract_line_comment_at_end_of_input() {
+        assert_eq!(extract_whitespaces("// done"), ("", "// done"));
+    }
+
+    #[test]
+    fn extract_nested_block_comments() {
+        assert_eq!(
+            extract_whitespaces("/* a /* b */ c */\n/**/x"),
+            ("x", "/* a /* b */ c */\n/**/"),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_unterminated_block_comment() {
+        assert_eq!(
+            extract_whitespaces(" /* a /* b */ c"),
+            ("/* a /* b */ c", " ")
+        );
+    }
+
+    #[test]
+    fn report_unterminated_block_comment_where_token_was_expected() {
+        assert_eq!(
+            extract_tag("}", "/* a"),
+            Err(ParseFailure::new("", "`*/` to end the comment")),
+        );
+    }
+
+    #[test]
+    fn extract_comment_as_required_whitespace() {
+        assert_eq!(
+            extract_required_whitespaces("/* c */x"),
+            Ok(("x", "/* c */"))
+        );
+    }
+
+    #[test]
+    fn extract_newline_as_statement_separator() {
+        assert_eq!(
+            extract_statement_separator(" // c\n  x"),
+            Ok(("x", " // c\n  "))
+        );
+    }
+
+    #[test]
+    fn extract_semicolons_as_statement_separator() {
+        assert_eq!(extract_statement_separator(" ; ;x"), Ok(("x", " ; ;")));
+    }
+
+    #[test]
+    fn do_not_extract_spaces_as_statement_separator() {
+        assert_eq!(
+            extract_statement_separator("  x"),
+            Err(ParseFailure::new("x", "newline or `;`")),
+        );
+    }
+
+    #[test]
+    fn extract_inline_spaces_up_to_newline() {
+        assert_eq!(extract_inline_whitespaces(" \t \n x"), ("\n x", " \t "));
+    }
+
     #[test]
     fn do_not_extract_spaces_when_input_does_not_start_with_them() {
         assert_eq!(
             extract_required_whitespaces("blah"),
-            Err("Expected space".to_string()),
+            Err(ParseFailure::new("blah", "whitespace")),
         );
     }
 
@@ -130,4 +550,142 @@ mod tests {
     fn extract_alphanumeric_ident() {
         assert_eq!(extract_identifier("foobar1()"), Ok(("()", "foobar1")));
     }
+
+    #[test]
+    fn do_not_extract_keyword_as_identifier() {
+        assert_eq!(
+            extract_identifier("let a"),
+            Err(ParseFailure::new("let a", "identifier")),
+        );
+    }
+
+    #[test]
+    fn extract_identifier_starting_with_keyword() {
+        assert_eq!(extract_identifier("letter"), Ok(("", "letter")));
+    }
+
+    #[test]
+    fn do_not_extract_missing_tag() {
+        assert_eq!(extract_tag("=", "1"), Err(ParseFailure::new("1", "`=`")));
+    }
+
+    #[test]
+    fn extract_keyword_word() {
+        assert_eq!(extract_keyword("true", "true && x"), Ok(" && x"));
+    }
+
+    #[test]
+    fn do_not_extract_keyword_prefix_of_identifier() {
+        assert_eq!(
+            extract_keyword("true", "truest"),
+            Err(ParseFailure::new("truest", "`true`")),
+        );
+    }
+
+    #[test]
+    fn extract_empty_list() {
+        assert_eq!(
+            extract_list("(", ")", extract_identifier, "( ) x"),
+            Ok((" x", vec![]))
+        );
+    }
+
+    #[test]
+    fn extract_list_of_identifiers() {
+        assert_eq!(
+            extract_list("(", ")", extract_identifier, "(a, b ,\n c,)"),
+            Ok(("", vec!["a", "b", "c"])),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_unclosed_list() {
+        assert_eq!(
+            extract_list("(", ")", extract_identifier, "(a b)"),
+            Err(ParseFailure::new("b)", "`,`").or(ParseFailure::new("b)", "`)`"))),
+        );
+    }
+
+    #[test]
+    fn extract_params_once_each() {
+        assert_eq!(
+            extract_params("(", ")", "(a, b)"),
+            Ok(("", vec!["a".to_string(), "b".to_string()])),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_duplicate_params() {
+        assert_eq!(
+            extract_params("|", "|", "|a, b, a| a"),
+            Err(ParseFailure::new("a| a", "a parameter name other than `a`")),
+        );
+    }
+
+    #[test]
+    fn extract_string_text_up_to_closing_quote() {
+        assert_eq!(
+            extract_string_text(r#"hello, world" + x"#),
+            Ok((r#"" + x"#, "hello, world".to_string())),
+        );
+    }
+
+    #[test]
+    fn extract_string_text_up_to_interpolation() {
+        assert_eq!(
+            extract_string_text(r#"a {b}""#),
+            Ok((r#"{b}""#, "a ".to_string())),
+        );
+    }
+
+    #[test]
+    fn extract_string_text_with_escapes() {
+        assert_eq!(
+            extract_string_text(r#"a\n\t\"b\"\\ \{\} \u{48}\u{1F600}""#),
+            Ok(("\"", "a\n\t\"b\"\\ {} H\u{1F600}".to_string())),
+        );
+    }
+
+    #[test]
+    fn extract_string_text_with_non_ascii_contents() {
+        assert_eq!(
+            extract_string_text("héllo→\"."),
+            Ok(("\".", "héllo→".to_string()))
+        );
+    }
+
+    #[test]
+    fn do_not_extract_unterminated_string_text() {
+        assert_eq!(
+            extract_string_text("abc"),
+            Err(ParseFailure::new("", "`\"` to end the string")),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_string_text_ending_in_backslash() {
+        assert_eq!(
+            extract_string_text("abc\\"),
+            Err(ParseFailure::new("", "`\"` to end the string")),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_unknown_escape() {
+        assert_eq!(
+            extract_string_text(r#"a\qb""#),
+            Err(ParseFailure::new(r#"\qb""#, "escape sequence")),
+        );
+    }
+
+    #[test]
+    fn do_not_extract_invalid_unicode_escape() {
+        assert_eq!(
+            extract_string_text(r#"\u{D800}""#),
+            Err(ParseFailure::new(
+                r#"\u{D800}""#,
+                "unicode escape such as `\\u{1F600}`",
+            )),
+        );
+    }
 }
@@ -1,16 +1,200 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+
+#[cfg(feature = "bigint")]
+use crate::bigint::BigInt;
+use crate::builtin::Builtin;
+use crate::closure::Closure;
+use crate::func_def::Function;
+use crate::list::List;
+use crate::map::Map;
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum Value {
-    Number(i32),
+    Number(i64),
+    /// Only ever holds integers outside the range of `i64`.
+    #[cfg(feature = "bigint")]
+    BigInt(BigInt),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    List(List),
+    Map(Map),
+    Range(Range),
+    Function(Rc<Function>),
+    Closure(Rc<Closure>),
+    Builtin(Builtin),
     Unit,
 }
 
-use std::fmt;
+/// The integers from `start` up to `end`, which is only part of the range if
+/// it is `inclusive`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub inclusive: bool,
+}
+
+impl Range {
+    pub fn iter(self) -> impl Iterator<Item = i64> {
+        (self.start..=self.end).take_while(move |&n| self.inclusive || n != self.end)
+    }
+
+    /// The first integer after the range, saturating at `i64::MAX`.
+    pub(crate) fn end_exclusive(self) -> i64 {
+        if self.inclusive {
+            self.end.saturating_add(1)
+        } else {
+            self.end
+        }
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dots = if self.inclusive { "..=" } else { ".." };
+        write!(f, "{}{}{}", self.start, dots, self.end)
+    }
+}
+
+impl Value {
+    pub fn list(items: Vec<Value>) -> Self {
+        Self::List(Rc::new(items))
+    }
+
+    pub fn map(entries: BTreeMap<String, Value>) -> Self {
+        Self::Map(Rc::new(entries))
+    }
+}
 
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Number(n) => write!(f, "{}", n),
+            #[cfg(feature = "bigint")]
+            Self::BigInt(n) => write!(f, "{}", n),
+            // Unlike `Display`, `Debug` always shows a fractional part or an
+            // exponent, so a float never looks like an integer.
+            Self::Float(x) => write!(f, "{:?}", x),
+            Self::Bool(b) => write!(f, "{}", b),
+            Self::String(s) => write_quoted(f, s),
+            Self::List(list) => {
+                write!(f, "[")?;
+                for (idx, item) in list.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "]")
+            }
+            Self::Map(map) => {
+                if map.is_empty() {
+                    return write!(f, "{{:}}");
+                }
+
+                write!(f, "{{")?;
+                for (idx, (key, value)) in map.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_quoted(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                write!(f, "}}")
+            }
+            Self::Function(function) => write!(f, "<fn {}>", function.name),
+            Self::Closure(_) => write!(f, "<closure>"),
+            Self::Builtin(builtin) => write!(f, "<builtin {}>", builtin.name()),
+            Self::Range(range) => write!(f, "{}", range),
             Self::Unit => write!(f, "Unit"),
         }
     }
 }
+
+/// Writes a string the way it would be written as a literal, so that it can
+/// be told apart from other values and pasted back into the REPL.
+fn write_quoted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+
+    for c in s.chars() {
+        match c {
+            '\n' => write!(f, "\\n")?,
+            '\t' => write!(f, "\\t")?,
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            // Only an opening brace starts an interpolation.
+            '{' => write!(f, "\\{{")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+
+    write!(f, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_float_with_fractional_part() {
+        assert_eq!(Value::Float(3.0).to_string(), "3.0");
+        assert_eq!(Value::Float(0.001).to_string(), "0.001");
+        assert_eq!(Value::Float(-1.5e100).to_string(), "-1.5e100");
+    }
+
+    #[test]
+    fn display_nested_list() {
+        assert_eq!(
+            Value::list(vec![
+                Value::Number(1),
+                Value::list(vec![Value::String("a".to_string())]),
+                Value::list(Vec::new()),
+            ])
+            .to_string(),
+            r#"[1, ["a"], []]"#,
+        );
+    }
+
+    #[test]
+    fn display_map_in_key_order() {
+        let entries = BTreeMap::from([
+            ("b".to_string(), Value::Number(2)),
+            ("a".to_string(), Value::map(BTreeMap::new())),
+        ]);
+
+        assert_eq!(Value::map(entries).to_string(), r#"{"a": {:}, "b": 2}"#);
+    }
+
+    #[test]
+    fn display_string_quoted() {
+        assert_eq!(Value::String("hi".to_string()).to_string(), "\"hi\"");
+    }
+
+    #[test]
+    fn display_string_that_parses_back() {
+        for text in ["}", "{x}", "{ \"a\" }"] {
+            let shown = Value::String(text.to_string()).to_string();
+            let (_, expression) = crate::expression::Expression::new(&shown).unwrap();
+
+            assert_eq!(
+                expression.eval(&crate::env::Env::default()),
+                Ok(Value::String(text.to_string())),
+                "{}",
+                shown,
+            );
+        }
+        assert_eq!(Value::String("}".to_string()).to_string(), r#""}""#);
+    }
+
+    #[test]
+    fn display_string_with_escapes() {
+        assert_eq!(
+            Value::String("a\"b\\c\nd\te\u{7}→{}".to_string()).to_string(),
+            r#""a\"b\\c\nd\te\u{7}→\{}""#,
+        );
+    }
+}